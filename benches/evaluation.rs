@@ -0,0 +1,71 @@
+use chess::{Board, MoveGen};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rm::Evaluator;
+use rusty_mind as rm;
+use std::str::FromStr;
+
+fn boards() -> Vec<Board> {
+    rm::BENCH_FENS
+        .iter()
+        .map(|fen| Board::from_str(fen).unwrap())
+        .collect()
+}
+
+fn evaluation(c: &mut Criterion) {
+    // Full evaluations of the bench positions, with a full window and a narrow window which
+    // lets the lazy evaluation skip the positional terms
+    let boards = boards();
+    let weights = rm::Weights::default();
+    let mut group = c.benchmark_group("evaluation");
+    group.bench_function("full_window", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(rm::evaluation(board, -999_999, 999_999, &weights));
+            }
+        })
+    });
+    group.bench_function("lazy", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(rm::evaluation(board, 2_000, 2_001, &weights));
+            }
+        })
+    });
+    group.bench_function("material", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(rm::material(board, &weights));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn incremental(c: &mut Criterion) {
+    // Every legal move of the bench positions made and taken back by the classical evaluator
+    let boards = boards();
+    let children: Vec<(Board, Vec<Board>)> = boards
+        .iter()
+        .map(|board| {
+            let next = MoveGen::new_legal(board)
+                .map(|mv| board.make_move_new(mv))
+                .collect();
+            (*board, next)
+        })
+        .collect();
+    let mut evaluator = rm::Classical::default();
+    c.bench_function("incremental_make_unmake", |b| {
+        b.iter(|| {
+            for (board, next) in &children {
+                evaluator.reset(board);
+                for child in next {
+                    evaluator.make_move(board, child);
+                    evaluator.unmake_move();
+                }
+            }
+        })
+    });
+}
+
+criterion_group!(benches, evaluation, incremental);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+use chess::{Board, ChessMove, MoveGen};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rusty_mind as rm;
+use std::str::FromStr;
+
+fn boards() -> Vec<Board> {
+    rm::BENCH_FENS
+        .iter()
+        .map(|fen| Board::from_str(fen).unwrap())
+        .collect()
+}
+
+fn move_picker(c: &mut Criterion) {
+    // The staged move picker returning all the moves and only the first one, as after a cutoff
+    let boards = boards();
+    let history = rm::History::new();
+    let tt_moves: Vec<Option<ChessMove>> = boards
+        .iter()
+        .map(|board| MoveGen::new_legal(board).last())
+        .collect();
+    let mut group = c.benchmark_group("move_picker");
+    group.bench_function("all_moves", |b| {
+        b.iter(|| {
+            for board in &boards {
+                let mut picker = rm::MovePicker::new(board, None, [None; 2]);
+                while let Some(mv) = picker.next(board, &history) {
+                    black_box(mv);
+                }
+            }
+        })
+    });
+    group.bench_function("first_move", |b| {
+        b.iter(|| {
+            for board in &boards {
+                let mut picker = rm::MovePicker::new(board, None, [None; 2]);
+                black_box(picker.next(board, &history));
+            }
+        })
+    });
+    group.bench_function("tt_move", |b| {
+        b.iter(|| {
+            for (board, tt_move) in boards.iter().zip(&tt_moves) {
+                let mut picker = rm::MovePicker::new(board, *tt_move, [None; 2]);
+                black_box(picker.next(board, &history));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn filter_moves(c: &mut Criterion) {
+    // Captures and checks for the quiescence search, with and without the checks
+    let boards = boards();
+    let mut group = c.benchmark_group("filter_moves");
+    for (name, depth) in [("with_checks", 0), ("captures", 10)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for board in &boards {
+                    black_box(rm::filter_moves(board, MoveGen::new_legal(board), depth, 1));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, move_picker, filter_moves);
+criterion_main!(benches);
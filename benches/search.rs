@@ -0,0 +1,50 @@
+use chess::Board;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rusty_mind as rm;
+use std::str::FromStr;
+
+// Bench positions with long capture sequences
+const TACTICAL: [usize; 7] = [3, 8, 9, 31, 34, 42, 43];
+
+// Hash size of the engines, big enough for the searches below
+const CACHE_SIZE: usize = 1 << 16;
+
+fn quiescence(c: &mut Criterion) {
+    // Every iteration starts with an empty hash so the searches do the same work
+    let mut group = c.benchmark_group("quiescence");
+    group.sample_size(20);
+    for index in TACTICAL {
+        let board = Board::from_str(rm::BENCH_FENS[index]).unwrap();
+        group.bench_function(format!("position_{}", index), |b| {
+            b.iter_batched_ref(
+                || rm::Engine::with_cache_size(CACHE_SIZE),
+                |engine| black_box(engine.quiescence(&board)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn negamax(c: &mut Criterion) {
+    // Fixed depth searches of the first bench positions: an opening, a middlegame and an endgame
+    let mut group = c.benchmark_group("negamax");
+    group.sample_size(10);
+    for depth in 1..=3 {
+        group.bench_function(format!("depth_{}", depth), |b| {
+            b.iter_batched_ref(
+                || rm::Engine::with_cache_size(CACHE_SIZE),
+                |engine| {
+                    for fen in &rm::BENCH_FENS[..3] {
+                        black_box(engine.search(&Board::from_str(fen).unwrap(), depth));
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, quiescence, negamax);
+criterion_main!(benches);
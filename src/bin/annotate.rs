@@ -0,0 +1,189 @@
+use chess::{Board, BoardStatus, ChessMove};
+use rusty_mind as rm;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+// Annotates the games of a PGN file with the engine
+// Usage: annotate <input.pgn> <output.pgn> [--time <ms>] [--depth <n>] [--hash <entries>]
+//
+// Every position is searched and the moves which lose too much compared to the engine's
+// choice are marked with ?! (inaccuracy), ? (mistake) or ?? (blunder).
+// The engine's preferred line is added as a variation and both moves get a {score/depth} comment
+
+// Centipawns lost by a move for each mark
+const INACCURACY: i32 = 50;
+const MISTAKE: i32 = 100;
+const BLUNDER: i32 = 300;
+// Scores are capped so mistakes in already decided positions are not marked
+const MAX_SCORE: i32 = 1_000;
+const MATE: i32 = 99_999;
+
+fn usage() -> ! {
+    eprintln!("Usage: annotate <input.pgn> <output.pgn> [options]");
+    eprintln!("    --time <ms> - search time per position (default 1000)");
+    eprintln!("    --depth <n> - maximum search depth per position");
+    eprintln!("    --hash <entries> - size of the hash, a power of two (default 1048576)");
+    process::exit(1);
+}
+
+fn parse_value(value: Option<String>) -> u64 {
+    match value.map(|x| x.parse::<u64>()) {
+        Some(Ok(value)) if value > 0 => value,
+        _ => usage(),
+    }
+}
+
+fn white_score(board: &Board, score: i32) -> i32 {
+    if board.side_to_move() == chess::Color::White {
+        score
+    } else {
+        -score
+    }
+}
+
+fn analyse(game: &mut rm::Game, time: Duration, depth: i32) -> (Option<rm::Analysis>, i32) {
+    // Analysis of the current position and its score relative to the side to move
+    match game.board.status() {
+        BoardStatus::Checkmate => (None, -MATE),
+        BoardStatus::Stalemate => (None, 0),
+        BoardStatus::Ongoing if rm::is_insufficient_material(&game.board) => (None, 0),
+        BoardStatus::Ongoing => {
+            let analysis = game.analyse(time, depth);
+            let score = analysis.as_ref().map_or(0, |x| x.score);
+            (analysis, score)
+        }
+    }
+}
+
+fn annotate(
+    pgn: &rm::PgnGame,
+    positions: &[(Board, ChessMove)],
+    game: &mut rm::Game,
+    time: Duration,
+    depth: i32,
+) -> rm::PgnGame {
+    // The moves were already replayed from the FEN, a game which can't be set up is copied as it is
+    if game
+        .set_board(pgn.tag("FEN").unwrap_or(rm::STARTING_FEN), Vec::new())
+        .is_err()
+    {
+        return pgn.clone();
+    }
+    let mut annotated = pgn.clone();
+    annotated
+        .tags
+        .push(("Annotator".to_string(), "Rusty Mind".to_string()));
+
+    let (mut analysis, mut score) = analyse(game, time, depth);
+    for (index, (board, played)) in positions.iter().enumerate() {
+        game.make_move(*played);
+        let (next_analysis, next_score) = analyse(game, time, depth);
+        let best = match analysis {
+            Some(best) => best,
+            None => break,
+        };
+        // Both scores from the point of view of the player who moved
+        let played_score = -next_score;
+        let loss = score.clamp(-MAX_SCORE, MAX_SCORE) - played_score.clamp(-MAX_SCORE, MAX_SCORE);
+        let nag = if best.best_move == *played {
+            None
+        } else if loss >= BLUNDER {
+            Some(4)
+        } else if loss >= MISTAKE {
+            Some(2)
+        } else if loss >= INACCURACY {
+            Some(6)
+        } else {
+            None
+        };
+
+        if let Some(nag) = nag {
+            let comment = rm::format_score(white_score(board, played_score), best.depth);
+            // The input may already have the same mark
+            if !annotated.nags.contains(&(index + 1, nag)) {
+                annotated.nags.push((index + 1, nag));
+            }
+            annotated.comments.push((index + 1, comment));
+
+            let mut line_board = *board;
+            let mut variation = rm::PgnGame::default();
+            for mv in &best.line {
+                variation.moves.push(rm::to_san(&line_board, *mv));
+                line_board = line_board.make_move_new(*mv);
+            }
+            let comment = rm::format_score(white_score(board, best.score), best.depth);
+            variation.comments.push((1, comment));
+            annotated.variations.push((index + 1, variation));
+        }
+        analysis = next_analysis;
+        score = next_score;
+    }
+    annotated
+}
+
+fn main() {
+    let mut files = Vec::new();
+    let mut time = None;
+    let mut depth = None;
+    let mut hash = 1 << 20;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time" => time = Some(Duration::from_millis(parse_value(args.next()))),
+            "--depth" => depth = Some(parse_value(args.next()) as i32),
+            "--hash" => hash = parse_value(args.next()) as usize,
+            _ if arg.starts_with("--") => usage(),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 || !hash.is_power_of_two() {
+        usage();
+    }
+    // A depth without a time searches to the full depth
+    let time = match (time, depth) {
+        (Some(time), _) => time,
+        (None, Some(_)) => Duration::MAX,
+        (None, None) => Duration::from_millis(1_000),
+    };
+    let depth = depth.unwrap_or(64);
+
+    let text = match fs::read_to_string(&files[0]) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("Could not read {}: {}", files[0], error);
+            process::exit(1);
+        }
+    };
+    let mut game = rm::Game::with_cache_size(hash);
+    // The scores written to the comments shouldn't include the random cushion of the root moves
+    game.set_tunable("RandomMargin", 0);
+    let mut output = String::new();
+    let games = rm::read_games(&text);
+    for (number, pgn) in games.iter().enumerate() {
+        match pgn.replay() {
+            Some(positions) => {
+                output.push_str(&annotate(pgn, &positions, &mut game, time, depth).to_pgn());
+                println!(
+                    "Game {}/{}: {} moves analysed",
+                    number + 1,
+                    games.len(),
+                    positions.len()
+                );
+            }
+            None => {
+                eprintln!(
+                    "Game {}/{}: illegal move, copied without annotations",
+                    number + 1,
+                    games.len()
+                );
+                output.push_str(&pgn.to_pgn());
+            }
+        }
+    }
+    if let Err(error) = fs::write(&files[1], output) {
+        eprintln!("Could not write {}: {}", files[1], error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,75 @@
+use rusty_mind as rm;
+use std::env;
+use std::fs;
+use std::process;
+
+// Builds a Polyglot opening book from PGN files
+// Usage: book <output.bin> <games.pgn>... [options]
+
+fn usage() -> ! {
+    eprintln!("Usage: book <output.bin> <games.pgn>... [options]");
+    eprintln!("    --min-games <n> - keep only moves played in at least n games (default 1)");
+    eprintln!("    --max-ply <n> - use only the first n plies of every game (default 30)");
+    eprintln!("    --win <w> --draw <d> --loss <l> - weights of the results (default 2 1 0)");
+    process::exit(1);
+}
+
+fn parse_value(value: Option<String>) -> u32 {
+    match value.map(|x| x.parse::<u32>()) {
+        Some(Ok(value)) => value,
+        _ => usage(),
+    }
+}
+
+fn main() {
+    let mut builder = rm::BookBuilder::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--min-games" => builder.min_games = parse_value(args.next()),
+            "--max-ply" => builder.max_ply = parse_value(args.next()) as usize,
+            "--win" => builder.win_weight = parse_value(args.next()),
+            "--draw" => builder.draw_weight = parse_value(args.next()),
+            "--loss" => builder.loss_weight = parse_value(args.next()),
+            _ if arg.starts_with("--") => usage(),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() < 2 {
+        usage();
+    }
+    let output = files.remove(0);
+
+    let mut games = 0;
+    let mut skipped = 0;
+    for file in files {
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("Could not read {}: {}", file, error);
+                process::exit(1);
+            }
+        };
+        for game in rm::read_games(&text) {
+            match game.replay() {
+                Some(positions) => {
+                    builder.add_game(&positions, &game.result);
+                    games += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+    }
+
+    match builder.write(&output) {
+        Ok(entries) => println!(
+            "{} games read, {} skipped, {} entries written to {}",
+            games, skipped, entries, output
+        ),
+        Err(error) => {
+            eprintln!("Could not write {}: {}", output, error);
+            process::exit(1);
+        }
+    }
+}
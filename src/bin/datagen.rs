@@ -0,0 +1,272 @@
+use chess::{Board, BoardStatus, CastleRights, ChessMove, Color, MoveGen, Piece, ALL_COLORS};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rusty_mind as rm;
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+// Generates training positions from games of the engine against itself
+// Usage: datagen [--games <n>] [--depth <n> | --nodes <n>] [--random-plies <n>] [--seed <n>]
+//                [--text <file>] [--binary <file>]
+//
+// The text format is EPD: the first four FEN fields followed by the search score
+// relative to the side to move and the game result, e.g.
+//     rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - ce -25; c9 "1/2-1/2";
+//
+// The binary format stores every position in 32 bytes (numbers are little endian):
+//     occupancy as u64,
+//     16 bytes of 4-bit piece codes (color * 6 + piece) in the order of the occupied squares,
+//     flags as u8 (bit 0 black to move, bits 1-4 castling rights KQkq),
+//     en passant file + 1 as u8 (0 if there is none),
+//     score relative to white as i16,
+//     result as u8 (0 black won, 1 draw, 2 white won),
+//     3 bytes of padding
+
+// Hash entries of the engine used for the games
+const CACHE_SIZE: usize = 1 << 20;
+// Games longer than this are adjudicated as a draw
+const MAX_PLIES: usize = 400;
+// Positions with a score beyond this are mates or tablebase wins and are not recorded
+const MAX_SCORE: i32 = 3_000;
+
+enum Limit {
+    Depth(i32),
+    Nodes(u64),
+}
+
+struct Sample {
+    // Position recorded during the game, the result is filled in when the game ends
+    board: Board,
+    score: i32,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: datagen [--games <n>] [--depth <n> | --nodes <n>] [--random-plies <n>]");
+    eprintln!("               [--seed <n>] [--text <file>] [--binary <file>]");
+    process::exit(1);
+}
+
+fn search(engine: &mut rm::Engine, board: &Board, limit: &Limit) -> Option<(ChessMove, i32)> {
+    // Fixed depth, or iterative deepening until the node budget is used up
+    // The first iteration always finishes so there is a move to play
+    match limit {
+        Limit::Depth(depth) => engine.search(board, *depth),
+        Limit::Nodes(nodes) => {
+            engine.reset_nodes();
+            let mut best = engine.search(board, 1);
+            engine.set_node_limit(Some(*nodes));
+            for depth in 2..64 {
+                match engine.search(board, depth) {
+                    Some(result) => best = Some(result),
+                    None => break,
+                }
+            }
+            engine.set_node_limit(None);
+            best
+        }
+    }
+}
+
+fn random_opening(rng: &mut StdRng, plies: usize) -> Option<Board> {
+    // Plays random moves from the starting position, None if the game ended on the way
+    let mut board = Board::default();
+    for _ in 0..plies {
+        let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        board = board.make_move_new(*moves.choose(rng)?);
+    }
+    if board.status() == BoardStatus::Ongoing {
+        Some(board)
+    } else {
+        None
+    }
+}
+
+fn play_game(engine: &mut rm::Engine, mut board: Board, limit: &Limit) -> (Vec<Sample>, f64) {
+    // Plays one game, returns the recorded positions and the result from white's point of view
+    let mut samples = Vec::new();
+    let mut repetitions: HashMap<u64, u32> = HashMap::new();
+    let mut halfmoves = 0;
+    for _ in 0..MAX_PLIES {
+        match board.status() {
+            BoardStatus::Checkmate => {
+                let result = if board.side_to_move() == Color::White {
+                    0.0
+                } else {
+                    1.0
+                };
+                return (samples, result);
+            }
+            BoardStatus::Stalemate => return (samples, 0.5),
+            BoardStatus::Ongoing => (),
+        }
+        let count = repetitions.entry(board.get_hash()).or_insert(0);
+        *count += 1;
+        if *count >= 3 || halfmoves >= 100 || rm::is_insufficient_material(&board) {
+            return (samples, 0.5);
+        }
+
+        let (mv, score) = match search(engine, &board, limit) {
+            Some(result) => result,
+            None => return (samples, 0.5),
+        };
+        let capture = board.piece_on(mv.get_dest()).is_some();
+        let pawn_move = board.piece_on(mv.get_source()) == Some(Piece::Pawn);
+        // Noisy positions are skipped: checks, tactical best moves and decided games
+        if board.checkers().popcnt() == 0
+            && !capture
+            && mv.get_promotion().is_none()
+            && score.abs() < MAX_SCORE
+        {
+            samples.push(Sample { board, score });
+        }
+        halfmoves = if capture || pawn_move {
+            0
+        } else {
+            halfmoves + 1
+        };
+        board = board.make_move_new(mv);
+    }
+    (samples, 0.5)
+}
+
+fn write_text(out: &mut impl Write, sample: &Sample, result: f64) -> io::Result<()> {
+    let fen = rm::board_to_fen(&sample.board, 0, 1);
+    let fields: Vec<&str> = fen.split_whitespace().take(4).collect();
+    let result = if result == 1.0 {
+        "1-0"
+    } else if result == 0.0 {
+        "0-1"
+    } else {
+        "1/2-1/2"
+    };
+    writeln!(
+        out,
+        "{} ce {}; c9 \"{}\";",
+        fields.join(" "),
+        sample.score,
+        result
+    )
+}
+
+fn write_binary(out: &mut impl Write, sample: &Sample, result: f64) -> io::Result<()> {
+    let board = &sample.board;
+    let mut bytes = [0u8; 32];
+    let occupancy = *board.combined();
+    bytes[0..8].copy_from_slice(&occupancy.0.to_le_bytes());
+    for (index, square) in occupancy.enumerate() {
+        let color = board.color_on(square).unwrap();
+        let piece = board.piece_on(square).unwrap();
+        let code = (color.to_index() * 6 + piece.to_index()) as u8;
+        bytes[8 + index / 2] |= code << (4 * (index % 2));
+    }
+    let mut flags = (board.side_to_move() == Color::Black) as u8;
+    for (color, shift) in ALL_COLORS.iter().zip([1, 3]) {
+        let rights = match board.castle_rights(*color) {
+            CastleRights::Both => 0b11,
+            CastleRights::KingSide => 0b01,
+            CastleRights::QueenSide => 0b10,
+            CastleRights::NoRights => 0b00,
+        };
+        flags |= rights << shift;
+    }
+    bytes[24] = flags;
+    bytes[25] = board
+        .en_passant()
+        .map_or(0, |square| square.get_file().to_index() as u8 + 1);
+    let white_score = if board.side_to_move() == Color::White {
+        sample.score
+    } else {
+        -sample.score
+    };
+    bytes[26..28].copy_from_slice(&(white_score as i16).to_le_bytes());
+    bytes[28] = (result * 2.0) as u8;
+    out.write_all(&bytes)
+}
+
+fn create(path: &Option<String>) -> Option<BufWriter<File>> {
+    let path = path.as_ref()?;
+    match File::create(path) {
+        Ok(file) => Some(BufWriter::new(file)),
+        Err(error) => {
+            eprintln!("Could not create {}: {}", path, error);
+            process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let mut games = 100;
+    let mut limit = Limit::Depth(4);
+    let mut random_plies = 8;
+    let mut seed = None;
+    let mut text_path = None;
+    let mut binary_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next().map(|x| x.parse::<u64>()) {
+            Some(Ok(value)) => value,
+            _ => usage(),
+        };
+        match arg.as_str() {
+            "--games" => games = value(),
+            "--depth" => limit = Limit::Depth(value().max(1) as i32),
+            "--nodes" => limit = Limit::Nodes(value()),
+            "--random-plies" => random_plies = value() as usize,
+            "--seed" => seed = Some(value()),
+            "--text" => text_path = args.next(),
+            "--binary" => binary_path = args.next(),
+            _ => usage(),
+        }
+    }
+    if text_path.is_none() && binary_path.is_none() {
+        usage();
+    }
+    let mut text = create(&text_path);
+    let mut binary = create(&binary_path);
+
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut engine = rm::Engine::with_cache_size(CACHE_SIZE);
+    // The scores are the labels of the positions, the random cushion of the root moves would be noise
+    // The variety comes from the random openings
+    engine.set_tunable("RandomMargin", 0);
+    let mut positions = 0;
+    for game in 1..=games {
+        let board = loop {
+            if let Some(board) = random_opening(&mut rng, random_plies) {
+                break board;
+            }
+        };
+        let (samples, result) = play_game(&mut engine, board, &limit);
+        for sample in &samples {
+            let written = text
+                .as_mut()
+                .map_or(Ok(()), |out| write_text(out, sample, result))
+                .and_then(|_| {
+                    binary
+                        .as_mut()
+                        .map_or(Ok(()), |out| write_binary(out, sample, result))
+                });
+            if let Err(error) = written {
+                eprintln!("Could not write the positions: {}", error);
+                process::exit(1);
+            }
+        }
+        positions += samples.len();
+        println!(
+            "Game {}/{}: result {}, {} positions in total",
+            game, games, result, positions
+        );
+    }
+    for out in [text.as_mut(), binary.as_mut()].into_iter().flatten() {
+        if let Err(error) = out.flush() {
+            eprintln!("Could not write the positions: {}", error);
+            process::exit(1);
+        }
+    }
+}
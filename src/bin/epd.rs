@@ -0,0 +1,156 @@
+use chess::ChessMove;
+use rusty_mind as rm;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+
+// Runs the engine on EPD test suites (WAC, STS, ECM, ...) and reports how many positions it solved
+// Usage: epd <suite.epd>... [--depth <n> | --time <ms>] [--hash <entries>]
+//
+// A position is solved if the engine plays one of the bm moves and none of the am moves
+// The moves are given in SAN, e.g.
+//     2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";
+
+enum Limit {
+    Depth(i32),
+    Time(Duration),
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: epd <suite.epd>... [--depth <n> | --time <ms>] [--hash <entries>]");
+    eprintln!("    --depth <n> - search every position to a fixed depth (default 3)");
+    eprintln!("    --time <ms> - deepen the search until the time is used up");
+    eprintln!("    --hash <entries> - size of the hash, a power of two (default 1048576)");
+    process::exit(1);
+}
+
+fn parse_value(value: Option<String>) -> u64 {
+    match value.map(|x| x.parse::<u64>()) {
+        Some(Ok(value)) if value > 0 => value,
+        _ => usage(),
+    }
+}
+
+fn search(
+    engine: &mut rm::Engine,
+    record: &rm::EpdRecord,
+    limit: &Limit,
+) -> Option<(ChessMove, i32)> {
+    // Fixed depth, or iterative deepening until the time limit has passed
+    // The search is stopped inside the tree, only the first iteration always finishes
+    match limit {
+        Limit::Depth(depth) => engine.search(&record.board, *depth),
+        Limit::Time(time) => {
+            let deadline = Instant::now() + *time;
+            let mut best = engine.search(&record.board, 1);
+            engine.set_deadline(Some(deadline));
+            for depth in 2..64 {
+                match engine.search(&record.board, depth) {
+                    Some(result) => best = Some(result),
+                    None => break,
+                }
+            }
+            engine.set_deadline(None);
+            best
+        }
+    }
+}
+
+fn main() {
+    let mut files = Vec::new();
+    let mut limit = Limit::Depth(3);
+    let mut hash = 1 << 20;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => limit = Limit::Depth(parse_value(args.next()) as i32),
+            "--time" => limit = Limit::Time(Duration::from_millis(parse_value(args.next()))),
+            "--hash" => hash = parse_value(args.next()) as usize,
+            _ if arg.starts_with("--") => usage(),
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() || !hash.is_power_of_two() {
+        usage();
+    }
+
+    let mut engine = rm::Engine::with_cache_size(hash);
+    let start = Instant::now();
+    let mut total = 0;
+    let mut solved = 0;
+    for file in files {
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("Could not read {}: {}", file, error);
+                process::exit(1);
+            }
+        };
+        let mut file_total = 0;
+        let mut file_solved = 0;
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = match rm::parse_epd(line) {
+                Some(record) => record,
+                None => {
+                    eprintln!("{}:{}: invalid position", file, number + 1);
+                    continue;
+                }
+            };
+            let best_moves = record.moves("bm");
+            let avoid_moves = record.moves("am");
+            let expected = record.operation("bm").unwrap_or_default().join(" ");
+            let avoided = record.operation("am").unwrap_or_default().join(" ");
+            // Skips the position if none of the moves could be read
+            if (best_moves.is_empty() && avoid_moves.is_empty())
+                || (!expected.is_empty() && best_moves.is_empty())
+            {
+                eprintln!("{}:{}: no valid bm or am moves", file, number + 1);
+                continue;
+            }
+            let id = record
+                .id()
+                .map_or(format!("{}:{}", file, number + 1), |id| id.to_string());
+            let found = search(&mut engine, &record, &limit);
+            let passed = found.is_some_and(|(mv, _)| {
+                (best_moves.is_empty() || best_moves.contains(&mv)) && !avoid_moves.contains(&mv)
+            });
+            let played = found.map_or("none".to_string(), |(mv, score)| {
+                format!("{} ({})", rm::to_san(&record.board, mv), score)
+            });
+            let mut report = format!(
+                "{} {}: played {}",
+                if passed { "pass" } else { "FAIL" },
+                id,
+                played
+            );
+            if !expected.is_empty() {
+                report += &format!(", bm {}", expected);
+            }
+            if !avoided.is_empty() {
+                report += &format!(", am {}", avoided);
+            }
+            println!("{}", report);
+            file_total += 1;
+            file_solved += passed as u32;
+        }
+        println!("{}: {}/{} solved\n", file, file_solved, file_total);
+        total += file_total;
+        solved += file_solved;
+    }
+    let percent = if total > 0 {
+        100.0 * solved as f64 / total as f64
+    } else {
+        0.0
+    };
+    println!(
+        "Total: {}/{} solved ({:.1}%) in {:.1} s",
+        solved,
+        total,
+        percent,
+        start.elapsed().as_secs_f64()
+    );
+}
@@ -0,0 +1,177 @@
+use chess::{Board, BoardStatus, Color};
+use rusty_mind as rm;
+use std::env;
+use std::fs;
+use std::process;
+use std::str::FromStr;
+use std::thread;
+
+// Tunes the evaluation weights with Texel's method
+// Usage: tune <positions.epd> <output.rs|output.toml> [--iterations <n>] [--step <n>]
+// The weights are written as a replacement for tables.rs, or as an EvalParams file
+// if the output ends with .toml
+// Every line of the input holds a FEN followed by the game result
+// ("1-0", "0-1", "1/2-1/2" or [1.0], [0.5], [0.0])
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: tune <positions.epd> <output.rs|output.toml> [--iterations <n>] [--step <n>]"
+    );
+    process::exit(1);
+}
+
+fn parse_line(line: &str) -> Option<(Board, f64)> {
+    // Reads the position and the result of the game from white's point of view
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 5 {
+        return None;
+    }
+    let fen = format!("{} 0 1", fields[..4].join(" "));
+    let board = Board::from_str(&fen).ok()?;
+    let rest = fields[4..].join(" ");
+    let result = if rest.contains("1/2-1/2") || rest.contains("[0.5]") {
+        0.5
+    } else if rest.contains("1-0") || rest.contains("[1.0]") {
+        1.0
+    } else if rest.contains("0-1") || rest.contains("[0.0]") {
+        0.0
+    } else {
+        return None;
+    };
+    Some((board, result))
+}
+
+fn sigmoid(eval: f64, k: f64) -> f64 {
+    // Expected score for an evaluation in centipawns
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+fn error(positions: &[(Board, f64)], weights: &rm::Weights, k: f64) -> f64 {
+    // Mean squared error between the results and the expected scores
+    // The positions are split between all the available cores
+    let threads = thread::available_parallelism().map_or(1, |x| x.get());
+    let chunk = positions.len().div_ceil(threads).max(1);
+    let total: f64 = thread::scope(|scope| {
+        let handles: Vec<_> = positions
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(board, result)| {
+                            let mut eval = rm::evaluation(board, -999_999, 999_999, weights);
+                            if board.side_to_move() == Color::Black {
+                                eval = -eval;
+                            }
+                            (result - sigmoid(eval as f64, k)).powi(2)
+                        })
+                        .sum::<f64>()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|x| x.join().unwrap()).sum()
+    });
+    total / positions.len() as f64
+}
+
+fn find_k(positions: &[(Board, f64)], weights: &rm::Weights) -> f64 {
+    // Finds the scaling constant which fits the current weights best
+    let mut best = (f64::MAX, 1.0);
+    let mut k = 0.1;
+    while k <= 3.0 {
+        let e = error(positions, weights, k);
+        if e < best.0 {
+            best = (e, k);
+        }
+        k += 0.05;
+    }
+    best.1
+}
+
+fn main() {
+    let mut files = Vec::new();
+    let mut iterations = 100;
+    let mut step = 1;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next().map(|x| x.parse::<i32>()) {
+            Some(Ok(value)) if value > 0 => value,
+            _ => usage(),
+        };
+        match arg.as_str() {
+            "--iterations" => iterations = value(),
+            "--step" => step = value(),
+            _ if arg.starts_with("--") => usage(),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 {
+        usage();
+    }
+
+    let text = match fs::read_to_string(&files[0]) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("Could not read {}: {}", files[0], error);
+            process::exit(1);
+        }
+    };
+    // Finished games don't depend on the weights
+    let positions: Vec<(Board, f64)> = text
+        .lines()
+        .filter_map(parse_line)
+        .filter(|(board, _)| board.status() == BoardStatus::Ongoing)
+        .collect();
+    if positions.is_empty() {
+        eprintln!("No positions found in {}", files[0]);
+        process::exit(1);
+    }
+    println!("{} positions loaded", positions.len());
+
+    let mut weights = rm::Weights::default();
+    let k = find_k(&positions, &weights);
+    let mut best_error = error(&positions, &weights, k);
+    println!("K = {:.2}, initial error {:.6}", k, best_error);
+
+    // Local search: every weight is moved by the step as long as the error keeps decreasing
+    // A table entry is read for both colors (mirrored for white), so the evaluation stays symmetric
+    // The lazy margin only skips work when the score is outside the window, with the full
+    // window used here it never changes the error
+    let count = weights.values_mut().len();
+    let lazy_margin = weights.tables().len() * 64
+        + weights
+            .scalars()
+            .iter()
+            .position(|(x, _)| *x == "LAZY_MARGIN")
+            .unwrap();
+    for iteration in 1..=iterations {
+        let mut improved = false;
+        for index in (0..count).filter(|&x| x != lazy_margin) {
+            for delta in [step, -step] {
+                let mut candidate = weights.clone();
+                *candidate.values_mut()[index] += delta;
+                let candidate_error = error(&positions, &candidate, k);
+                if candidate_error < best_error {
+                    best_error = candidate_error;
+                    weights = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        println!("Iteration {}: error {:.6}", iteration, best_error);
+        let output = if files[1].ends_with(".toml") {
+            weights.to_toml()
+        } else {
+            weights.to_source()
+        };
+        if let Err(error) = fs::write(&files[1], output) {
+            eprintln!("Could not write {}: {}", files[1], error);
+            process::exit(1);
+        }
+        if !improved {
+            break;
+        }
+    }
+    println!("Weights written to {}", files[1]);
+}
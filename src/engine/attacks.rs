@@ -0,0 +1,347 @@
+use once_cell::sync::Lazy;
+
+// Attack tables of the move generator in position.rs
+// Squares are numbered from a1 = 0 to h8 = 63 like in the chess crate, bitboards are plain u64
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const KNIGHT_STEPS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_STEPS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+// Magic numbers for the rook and bishop attack tables, found by trying sparse random numbers
+const ROOK_MAGICS: [u64; 64] = [
+    0x2080002080400010,
+    0x00C0002001401000,
+    0x2100110008402002,
+    0x0880080081041000,
+    0x0200020020041008,
+    0x2300040008010012,
+    0x0C00283004008201,
+    0x0180010000407A80,
+    0x0168800080400020,
+    0x0010400040201000,
+    0x1001002001001048,
+    0x1001002408100100,
+    0x0801000408010012,
+    0x4001000209000400,
+    0x08A20004C8020001,
+    0x2002801145002280,
+    0x0080860021004200,
+    0x001000C009402002,
+    0x00B0002004002800,
+    0x100A808010020800,
+    0x9400808004000800,
+    0x0090808004000200,
+    0x0000040010810208,
+    0x2000020000448534,
+    0x4104400480008033,
+    0x0000810100204000,
+    0x0440430900200010,
+    0x4600240900100100,
+    0x0804080100110004,
+    0x0001000300080400,
+    0x0004084400011002,
+    0x0023040200008041,
+    0x0580050043002080,
+    0x0400804002802008,
+    0x0001002001004010,
+    0x0080200A02001040,
+    0x600D480280802400,
+    0x400B800201800C00,
+    0x2408211004004208,
+    0x0200211082000844,
+    0x0020804010208000,
+    0x5030004020104000,
+    0xA042084080220010,
+    0x4088080010008080,
+    0x5002080100110004,
+    0x2012002010040400,
+    0x0040318210440008,
+    0x0120941040820001,
+    0x1000800100402100,
+    0x0040002010004840,
+    0x8108450020001900,
+    0x0200204008120200,
+    0x0080800C00180180,
+    0x0885000400420900,
+    0x230802011008C400,
+    0x3801740891432200,
+    0x0A00250212024082,
+    0x0000882040001105,
+    0x0042102082000A42,
+    0xC401210810000501,
+    0x0241001002480005,
+    0x0081000400880241,
+    0x0000009008024124,
+    0x0048122980410402,
+];
+const BISHOP_MAGICS: [u64; 64] = [
+    0x1862221006220044,
+    0x2104A14202020060,
+    0x2804081220444001,
+    0x2102408900010001,
+    0x0002021000040002,
+    0x08C3100805004300,
+    0x1084040124920050,
+    0x8900440043382010,
+    0x2401410802140040,
+    0x0901200454208020,
+    0x0000090216020541,
+    0x1283844040800804,
+    0x0521840420000803,
+    0x0800010402400C40,
+    0x0000408E10100404,
+    0x0009810048420800,
+    0x2004211004286808,
+    0x13080A1001380080,
+    0x0008801004220020,
+    0x0024000802480800,
+    0x1461001190400401,
+    0x0020400200500440,
+    0x0003000409019000,
+    0x000C20820D011802,
+    0x000804002164100C,
+    0x00048400A0011404,
+    0x5018110308044100,
+    0x0048A00804010020,
+    0x0007840000802000,
+    0x8808A20075004220,
+    0x8014040000822100,
+    0x110C03000E251101,
+    0x0081094820202010,
+    0x0008041000044100,
+    0x00C1202808940800,
+    0x8108100821040400,
+    0x1240010010010041,
+    0x0810004080011000,
+    0x00A20C0401804A00,
+    0x40014C0020050500,
+    0x5805082012042480,
+    0x2004022144031000,
+    0x2082002024204808,
+    0x0800004200800800,
+    0x0410020204100A02,
+    0x80C1204080804101,
+    0x0010104E01800042,
+    0x000800810C400208,
+    0x100080B008201210,
+    0x8000440605112101,
+    0x000202008C440040,
+    0x9004002210442200,
+    0x2032014088222045,
+    0x0C00202222C20000,
+    0x0140040820A50100,
+    0x0222104C29024018,
+    0x0200110121202004,
+    0x0800104200B00802,
+    0x0000401424020801,
+    0x4000000004208840,
+    0x0802E00040104100,
+    0x03000020A0424080,
+    0x0011C00408188121,
+    0x0848020822040013,
+];
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    // Relevant occupancy of a slider on one square and where its attacks start in the table
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+struct Tables {
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    sliders: Vec<u64>,
+    knight: [u64; 64],
+    king: [u64; 64],
+    pawn: [[u64; 64]; 2],
+    between: Vec<[u64; 64]>,
+}
+
+static TABLES: Lazy<Tables> = Lazy::new(Tables::new);
+
+pub fn rook_attacks(square: usize, occupied: u64) -> u64 {
+    TABLES.sliders[TABLES.rook[square].index(occupied)]
+}
+
+pub fn bishop_attacks(square: usize, occupied: u64) -> u64 {
+    TABLES.sliders[TABLES.bishop[square].index(occupied)]
+}
+
+pub fn queen_attacks(square: usize, occupied: u64) -> u64 {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+pub fn knight_attacks(square: usize) -> u64 {
+    TABLES.knight[square]
+}
+
+pub fn king_attacks(square: usize) -> u64 {
+    TABLES.king[square]
+}
+
+pub fn pawn_attacks(color: usize, square: usize) -> u64 {
+    // Squares attacked by a pawn of the given color (0 = white, 1 = black)
+    TABLES.pawn[color][square]
+}
+
+pub fn between(from: usize, to: usize) -> u64 {
+    // The squares between two squares on the same line, empty if they aren't on a line
+    TABLES.between[from][to]
+}
+
+impl Magic {
+    fn index(&self, occupied: u64) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+impl Tables {
+    fn new() -> Tables {
+        let mut tables = Tables {
+            rook: [Magic::default(); 64],
+            bishop: [Magic::default(); 64],
+            sliders: Vec::new(),
+            knight: [0; 64],
+            king: [0; 64],
+            pawn: [[0; 64]; 2],
+            between: vec![[0; 64]; 64],
+        };
+        for square in 0..64 {
+            tables.rook[square] = fill_magic(
+                square,
+                &ROOK_DIRECTIONS,
+                ROOK_MAGICS[square],
+                &mut tables.sliders,
+            );
+            tables.bishop[square] = fill_magic(
+                square,
+                &BISHOP_DIRECTIONS,
+                BISHOP_MAGICS[square],
+                &mut tables.sliders,
+            );
+            tables.knight[square] = steps(square, &KNIGHT_STEPS);
+            tables.king[square] = steps(square, &KING_STEPS);
+            tables.pawn[0][square] = steps(square, &[(-1, 1), (1, 1)]);
+            tables.pawn[1][square] = steps(square, &[(-1, -1), (1, -1)]);
+            for (file, rank) in ROOK_DIRECTIONS.iter().chain(BISHOP_DIRECTIONS.iter()) {
+                let mut squares = 0;
+                let mut current = square;
+                while let Some(target) = offset(current, *file, *rank) {
+                    tables.between[square][target] = squares;
+                    squares |= 1 << target;
+                    current = target;
+                }
+            }
+        }
+        tables
+    }
+}
+
+fn offset(square: usize, file: i32, rank: i32) -> Option<usize> {
+    // The square at the given file and rank distance, None if it is off the board
+    let file = (square % 8) as i32 + file;
+    let rank = (square / 8) as i32 + rank;
+    if (0..8).contains(&file) && (0..8).contains(&rank) {
+        Some((rank * 8 + file) as usize)
+    } else {
+        None
+    }
+}
+
+fn steps(square: usize, steps: &[(i32, i32)]) -> u64 {
+    steps
+        .iter()
+        .filter_map(|(file, rank)| offset(square, *file, *rank))
+        .fold(0, |bitboard, target| bitboard | 1 << target)
+}
+
+fn sliding_attacks(square: usize, occupied: u64, directions: &[(i32, i32)]) -> u64 {
+    // Attacks found by walking the rays until they hit a piece, used to fill the tables
+    let mut attacks = 0;
+    for (file, rank) in directions {
+        let mut current = square;
+        while let Some(target) = offset(current, *file, *rank) {
+            attacks |= 1 << target;
+            if occupied & 1 << target != 0 {
+                break;
+            }
+            current = target;
+        }
+    }
+    attacks
+}
+
+fn relevant_mask(square: usize, directions: &[(i32, i32)]) -> u64 {
+    // The squares whose occupancy changes the attacks, the last square of every ray never does
+    let mut mask = 0;
+    for (file, rank) in directions {
+        let mut current = square;
+        while let Some(target) = offset(current, *file, *rank) {
+            if offset(target, *file, *rank).is_none() {
+                break;
+            }
+            mask |= 1 << target;
+            current = target;
+        }
+    }
+    mask
+}
+
+fn fill_magic(
+    square: usize,
+    directions: &[(i32, i32)],
+    magic: u64,
+    sliders: &mut Vec<u64>,
+) -> Magic {
+    // Stores the attacks for every occupancy of the relevant squares at the index given by the magic
+    let mask = relevant_mask(square, directions);
+    let bits = mask.count_ones();
+    let magic = Magic {
+        mask,
+        magic,
+        shift: 64 - bits,
+        offset: sliders.len(),
+    };
+    sliders.resize(magic.offset + (1 << bits), 0);
+    let mut filled = vec![false; 1 << bits];
+    // Enumerates the subsets of the mask
+    let mut occupied: u64 = 0;
+    loop {
+        let attacks = sliding_attacks(square, occupied, directions);
+        let index = magic.index(occupied);
+        assert!(
+            !filled[index - magic.offset] || sliders[index] == attacks,
+            "Wrong magic number for square {}",
+            square
+        );
+        filled[index - magic.offset] = true;
+        sliders[index] = attacks;
+        occupied = occupied.wrapping_sub(mask) & mask;
+        if occupied == 0 {
+            break;
+        }
+    }
+    magic
+}
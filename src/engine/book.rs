@@ -0,0 +1,318 @@
+use chess::{
+    get_adjacent_files, get_rank, Board, CastleRights, ChessMove, Color, MoveGen, Piece, Square,
+    ALL_COLORS, ALL_SQUARES, EMPTY,
+};
+use rand::prelude::*;
+use shakmaty::zobrist::{Zobrist64, ZobristValue};
+use shakmaty::CastlingSide;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Size of one entry in a Polyglot book file
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    // One record of a Polyglot book, all the numbers are stored as big endian
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8]) -> BookEntry {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mv: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+pub struct Book {
+    // Entries sorted by the key so the position can be found by a binary search
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Book> {
+        // Reads a Polyglot .bin file
+        let bytes = fs::read(path)?;
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the file is not a Polyglot book",
+            ));
+        }
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(BookEntry::from_bytes)
+            .collect();
+        // Books should already be sorted, sorting again is cheap and makes the lookup safe
+        entries.sort_by_key(|entry| entry.key);
+        Ok(Book { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn moves(&self, board: &Board) -> Vec<(ChessMove, u16)> {
+        // Returns the legal book moves in the position together with their weights
+        let key = polyglot_key(board);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| decode_move(board, entry.mv).map(|mv| (mv, entry.weight)))
+            .collect()
+    }
+
+    pub fn pick(&self, board: &Board) -> Option<ChessMove> {
+        // Selects a book move randomly, the probability is proportional to its weight
+        let moves = self.moves(board);
+        let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+        if total == 0 {
+            return moves.first().map(|(mv, _)| *mv);
+        }
+        let mut choice = thread_rng().gen_range(0..total);
+        for (mv, weight) in moves {
+            if choice < weight as u32 {
+                return Some(mv);
+            }
+            choice -= weight as u32;
+        }
+        None
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct MoveStats {
+    // Statistics of one move in one position
+    games: u32,
+    // Sum of the result weights from the point of view of the side which played the move
+    score: u32,
+}
+
+pub struct BookBuilder {
+    // Aggregates moves from many games and turns them into a Polyglot book
+    stats: HashMap<(u64, u16), MoveStats>,
+    // Only moves played in at least this many games are written
+    pub min_games: u32,
+    // Only the first plies of every game are used
+    pub max_ply: usize,
+    // Weights of a win, a draw and a loss for the side which played the move
+    pub win_weight: u32,
+    pub draw_weight: u32,
+    pub loss_weight: u32,
+}
+
+impl Default for BookBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BookBuilder {
+    pub fn new() -> BookBuilder {
+        // The default weights are the ones used by Polyglot itself
+        BookBuilder {
+            stats: HashMap::new(),
+            min_games: 1,
+            max_ply: 30,
+            win_weight: 2,
+            draw_weight: 1,
+            loss_weight: 0,
+        }
+    }
+
+    pub fn add_game(&mut self, positions: &[(Board, ChessMove)], result: &str) {
+        // Records the moves of one game, games without a result are skipped
+        let (white_score, black_score) = match result {
+            "1-0" => (self.win_weight, self.loss_weight),
+            "0-1" => (self.loss_weight, self.win_weight),
+            "1/2-1/2" => (self.draw_weight, self.draw_weight),
+            _ => return,
+        };
+        for (board, mv) in positions.iter().take(self.max_ply) {
+            let stats = self
+                .stats
+                .entry((polyglot_key(board), encode_move(board, *mv)))
+                .or_default();
+            stats.games += 1;
+            stats.score += if board.side_to_move() == Color::White {
+                white_score
+            } else {
+                black_score
+            };
+        }
+    }
+
+    pub fn entries(&self) -> Vec<BookEntry> {
+        // Returns the book entries sorted by the key and then by the weight
+        // The weights are scaled down if they don't fit into 16 bits
+        let max_score = self.stats.values().map(|x| x.score).max().unwrap_or(0);
+        let scale = (max_score as f64 / u16::MAX as f64).max(1.0);
+        let mut entries: Vec<BookEntry> = self
+            .stats
+            .iter()
+            .filter(|(_, stats)| stats.games >= self.min_games)
+            .map(|((key, mv), stats)| BookEntry {
+                key: *key,
+                mv: *mv,
+                weight: (stats.score as f64 / scale) as u16,
+                learn: 0,
+            })
+            .filter(|entry| entry.weight > 0)
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        entries
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<usize> {
+        // Writes the book into a Polyglot .bin file, returns the number of entries
+        let entries = self.entries();
+        let bytes: Vec<u8> = entries.iter().flat_map(|entry| entry.to_bytes()).collect();
+        fs::write(path, bytes)?;
+        Ok(entries.len())
+    }
+}
+
+pub fn polyglot_key(board: &Board) -> u64 {
+    // Computes the Polyglot hash of the board
+    // The random numbers are the Polyglot ones which are shared with the shakmaty crate
+    let mut key = Zobrist64::default();
+    for color in ALL_COLORS {
+        for square in *board.color_combined(color) {
+            let piece = board.piece_on(square).unwrap();
+            key ^= Zobrist64::zobrist_for_piece(
+                shakmaty::Square::new(square.to_int() as u32),
+                shakmaty::Piece {
+                    color: to_color(color),
+                    role: to_role(piece),
+                },
+            );
+        }
+    }
+    for color in ALL_COLORS {
+        let sides = match board.castle_rights(color) {
+            CastleRights::Both => &[CastlingSide::KingSide, CastlingSide::QueenSide][..],
+            CastleRights::KingSide => &[CastlingSide::KingSide][..],
+            CastleRights::QueenSide => &[CastlingSide::QueenSide][..],
+            CastleRights::NoRights => &[][..],
+        };
+        for side in sides {
+            key ^= Zobrist64::zobrist_for_castling_right(to_color(color), *side);
+        }
+    }
+    // The en passant file is hashed only if a pawn can actually capture
+    // The crate stores the square of the pawn which can be captured
+    if let Some(square) = board.en_passant() {
+        let attackers = get_adjacent_files(square.get_file())
+            & get_rank(square.get_rank())
+            & board.pieces(Piece::Pawn)
+            & board.color_combined(board.side_to_move());
+        if attackers != EMPTY {
+            key ^= Zobrist64::zobrist_for_en_passant_file(shakmaty::File::new(
+                square.get_file().to_index() as u32,
+            ));
+        }
+    }
+    if board.side_to_move() == Color::White {
+        key ^= Zobrist64::zobrist_for_white_turn();
+    }
+    key.0
+}
+
+pub fn encode_move(board: &Board, mv: ChessMove) -> u16 {
+    // Converts a move to the Polyglot format
+    // Castling is stored as the king capturing its own rook
+    let source = mv.get_source();
+    let mut dest = mv.get_dest();
+    if board.piece_on(source) == Some(Piece::King)
+        && source
+            .get_file()
+            .to_index()
+            .abs_diff(dest.get_file().to_index())
+            == 2
+    {
+        let file = if dest.get_file() > source.get_file() {
+            chess::File::H
+        } else {
+            chess::File::A
+        };
+        dest = Square::make_square(dest.get_rank(), file);
+    }
+    let promotion = match mv.get_promotion() {
+        None => 0,
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(_) => 4,
+    };
+    (promotion << 12) | ((source.to_int() as u16) << 6) | dest.to_int() as u16
+}
+
+pub fn decode_move(board: &Board, mv: u16) -> Option<ChessMove> {
+    // Converts a Polyglot move into a legal move in the position
+    let square = |bits: u16| ALL_SQUARES[(bits & 0x3f) as usize];
+    let source = square(mv >> 6);
+    let mut dest = square(mv);
+    let promotion = match (mv >> 12) & 0x7 {
+        0 => None,
+        1 => Some(Piece::Knight),
+        2 => Some(Piece::Bishop),
+        3 => Some(Piece::Rook),
+        4 => Some(Piece::Queen),
+        _ => return None,
+    };
+    // King takes its own rook means castling
+    if board.piece_on(source) == Some(Piece::King)
+        && board.piece_on(dest) == Some(Piece::Rook)
+        && board.color_on(dest) == Some(board.side_to_move())
+    {
+        let file = if dest.get_file() > source.get_file() {
+            chess::File::G
+        } else {
+            chess::File::C
+        };
+        dest = Square::make_square(dest.get_rank(), file);
+    }
+    let chess_move = ChessMove::new(source, dest, promotion);
+    MoveGen::new_legal(board).find(|legal| *legal == chess_move)
+}
+
+fn to_color(color: Color) -> shakmaty::Color {
+    match color {
+        Color::White => shakmaty::Color::White,
+        Color::Black => shakmaty::Color::Black,
+    }
+}
+
+fn to_role(piece: Piece) -> shakmaty::Role {
+    match piece {
+        Piece::Pawn => shakmaty::Role::Pawn,
+        Piece::Knight => shakmaty::Role::Knight,
+        Piece::Bishop => shakmaty::Role::Bishop,
+        Piece::Rook => shakmaty::Role::Rook,
+        Piece::Queen => shakmaty::Role::Queen,
+        Piece::King => shakmaty::Role::King,
+    }
+}
@@ -0,0 +1,236 @@
+use super::fen::Fen as BoardFen;
+use super::movepick::least_valuable_attacker;
+use chess::{
+    between, BitBoard, Board, BoardBuilder, ChessMove, Color, File, Piece, Square, ALL_SQUARES,
+    EMPTY,
+};
+use shakmaty::fen::Fen;
+use shakmaty::san::{San, SanPlus};
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, CastlingSide, Chess, EnPassantMode, Move, Position};
+use std::str::FromStr;
+
+#[derive(Clone, Debug)]
+pub struct Chess960 {
+    // Position of a Chess960 game
+    // The chess crate only castles with the rooks in the corners, so the castling rights and
+    // the castling moves are kept here and the Board given to the search has no castling rights
+    position: Chess,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CastlingRooks {
+    // Chess960 castling rights as the squares of the rooks which can still castle,
+    // by color and side (king side first)
+    // The search keeps them next to the Board, which has no castling rights in Chess960
+    rooks: [[Option<Square>; 2]; 2],
+}
+
+impl CastlingRooks {
+    pub fn is_empty(&self) -> bool {
+        self.rooks == [[None; 2]; 2]
+    }
+
+    pub fn key(&self) -> u64 {
+        // Mixed into the hash of the board, 0 without castling rights
+        let mut key = 0;
+        for (index, rook) in self.rooks.iter().flatten().enumerate() {
+            if let Some(square) = rook {
+                key |= (square.to_index() as u64 + 1) << (8 * index);
+            }
+        }
+        key.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+
+    pub fn after(&self, board: &Board, chess_move: ChessMove) -> CastlingRooks {
+        // The rights after a move: moving the king loses both, moving or losing a rook loses its own
+        let mut rooks = *self;
+        if self.is_empty() {
+            return rooks;
+        }
+        if board.piece_on(chess_move.get_source()) == Some(Piece::King) {
+            rooks.rooks[board.side_to_move().to_index()] = [None; 2];
+        }
+        for rook in rooks.rooks.iter_mut().flatten() {
+            if *rook == Some(chess_move.get_source()) || *rook == Some(chess_move.get_dest()) {
+                *rook = None;
+            }
+        }
+        rooks
+    }
+
+    pub fn castles(&self, board: &Board) -> Vec<(ChessMove, Board)> {
+        // The legal castling moves in the king-takes-rook form together with the boards after them
+        let mut castles = Vec::new();
+        let color = board.side_to_move();
+        if self.rooks[color.to_index()] == [None; 2] || board.checkers() != &EMPTY {
+            return castles;
+        }
+        let king = board.king_square(color);
+        for (side, rook) in self.rooks[color.to_index()].iter().enumerate() {
+            let Some(rook) = *rook else { continue };
+            let (king_file, rook_file) = if side == 0 {
+                (File::G, File::F)
+            } else {
+                (File::C, File::D)
+            };
+            let king_dest = Square::make_square(king.get_rank(), king_file);
+            let rook_dest = Square::make_square(king.get_rank(), rook_file);
+            // Every square the king or the rook crosses has to be empty apart from themselves
+            let king_path = between(king, king_dest) | BitBoard::from_square(king_dest);
+            let rook_path = between(rook, rook_dest) | BitBoard::from_square(rook_dest);
+            let others =
+                board.combined() ^ BitBoard::from_square(king) ^ BitBoard::from_square(rook);
+            if (king_path | rook_path) & others != EMPTY {
+                continue;
+            }
+            // The king can't cross an attacked square, the destination is checked on the new board
+            if between(king, king_dest).into_iter().any(|square| {
+                least_valuable_attacker(board, square, *board.combined(), !color).is_some()
+            }) {
+                continue;
+            }
+            let mut builder = BoardBuilder::from(board);
+            builder
+                .clear_square(king)
+                .clear_square(rook)
+                .piece(king_dest, Piece::King, color)
+                .piece(rook_dest, Piece::Rook, color)
+                .side_to_move(!color)
+                .en_passant(None);
+            // The board is rejected if the king ends in check
+            if let Ok(after) = Board::try_from(&builder) {
+                castles.push((ChessMove::new(king, rook, None), after));
+            }
+        }
+        castles
+    }
+}
+
+impl Chess960 {
+    pub fn from_fen(fen: &str) -> Result<Chess960, String> {
+        // Reads a FEN with X-FEN or Shredder-FEN castling rights
+        let fen: Fen = fen
+            .parse()
+            .map_err(|e: shakmaty::fen::ParseFenError| e.to_string())?;
+        let position = fen
+            .into_position(CastlingMode::Chess960)
+            .map_err(|e| e.to_string())?;
+        Ok(Chess960 { position })
+    }
+
+    pub fn fen(&self) -> String {
+        // X-FEN of the position, the castling rights use KQkq unless a rook between
+        // the king and the outermost rook makes them ambiguous
+        Fen::from_position(&self.position, EnPassantMode::Legal).to_string()
+    }
+
+    pub fn uncastled(&self) -> BoardFen {
+        // The position without castling rights, as the chess crate can read it
+        let fen = self.fen();
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+        fields[2] = "-";
+        BoardFen::parse(&fields.join(" ")).unwrap()
+    }
+
+    pub fn castling_moves(&self) -> Vec<ChessMove> {
+        // The legal castling moves in the king-takes-rook form
+        self.position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.is_castle())
+            .filter_map(to_chess_move)
+            .collect()
+    }
+
+    pub fn castling_rooks(&self) -> CastlingRooks {
+        // The castling rights of the position for the search
+        let mut rooks = CastlingRooks::default();
+        for color in [Color::White, Color::Black] {
+            let shakmaty_color = if color == Color::White {
+                shakmaty::Color::White
+            } else {
+                shakmaty::Color::Black
+            };
+            for (side, castling_side) in [CastlingSide::KingSide, CastlingSide::QueenSide]
+                .into_iter()
+                .enumerate()
+            {
+                rooks.rooks[color.to_index()][side] = self
+                    .position
+                    .castles()
+                    .rook(shakmaty_color, castling_side)
+                    .map(|square| ALL_SQUARES[square.to_usize()]);
+            }
+        }
+        rooks
+    }
+
+    pub fn parse_move(&self, text: &str) -> Option<ChessMove> {
+        // Reads a move in coordinate notation or SAN
+        // Castling is written as the king taking its own rook, e.g. b1a1, or as O-O and O-O-O
+        let mv = match text.parse::<UciMove>() {
+            Ok(uci) => uci.to_move(&self.position).ok(),
+            Err(_) => None,
+        }
+        .or_else(|| {
+            San::from_ascii(text.trim_end_matches(['+', '#']).as_bytes())
+                .ok()?
+                .to_move(&self.position)
+                .ok()
+        })?;
+        to_chess_move(mv)
+    }
+
+    pub fn to_san(&self, chess_move: ChessMove) -> String {
+        match self.to_move(chess_move) {
+            Some(mv) => {
+                SanPlus::from_move_and_play_unchecked(&mut self.position.clone(), mv).to_string()
+            }
+            None => chess_move.to_string(),
+        }
+    }
+
+    pub fn make_move(&mut self, chess_move: ChessMove) -> bool {
+        // Plays a legal move, returns false if the move isn't legal
+        match self.to_move(chess_move) {
+            Some(mv) => {
+                self.position.play_unchecked(mv);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.position.is_checkmate()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        self.position.is_stalemate()
+    }
+
+    fn to_move(&self, chess_move: ChessMove) -> Option<Move> {
+        chess_move
+            .to_string()
+            .parse::<UciMove>()
+            .ok()?
+            .to_move(&self.position)
+            .ok()
+    }
+}
+
+fn to_chess_move(mv: Move) -> Option<ChessMove> {
+    // Converts a move of the shakmaty crate, castling becomes the king's move to the rook's square
+    let uci = mv.to_uci(CastlingMode::Chess960).to_string();
+    let source = Square::from_str(uci.get(0..2)?).ok()?;
+    let dest = Square::from_str(uci.get(2..4)?).ok()?;
+    let promotion = match uci.get(4..) {
+        Some("q") => Some(chess::Piece::Queen),
+        Some("r") => Some(chess::Piece::Rook),
+        Some("b") => Some(chess::Piece::Bishop),
+        Some("n") => Some(chess::Piece::Knight),
+        _ => None,
+    };
+    Some(ChessMove::new(source, dest, promotion))
+}
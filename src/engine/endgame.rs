@@ -0,0 +1,215 @@
+use super::evaluation::LIGHT_SQUARES;
+use chess::{get_file, BitBoard, Board, Color, File, Piece, Rank, Square, EMPTY};
+
+// Evaluation given to a won endgame before the mate is found by the search
+// It has to stay well below the checkmate score
+const KNOWN_WIN: i32 = 5_000;
+
+// Scale factors are in 1/64ths of the evaluation
+pub const SCALE_NORMAL: i32 = 64;
+const SCALE_OPPOSITE_BISHOPS: i32 = 32;
+
+struct Material {
+    // Number of pieces of each type for one side, the king isn't counted
+    pawns: u32,
+    knights: u32,
+    bishops: u32,
+    rooks: u32,
+    queens: u32,
+}
+
+impl Material {
+    fn new(board: &Board, color: Color) -> Material {
+        let count = |piece: Piece| (*board.pieces(piece) & board.color_combined(color)).popcnt();
+        Material {
+            pawns: count(Piece::Pawn),
+            knights: count(Piece::Knight),
+            bishops: count(Piece::Bishop),
+            rooks: count(Piece::Rook),
+            queens: count(Piece::Queen),
+        }
+    }
+
+    fn minors(&self) -> u32 {
+        self.knights + self.bishops
+    }
+
+    fn is_bare_king(&self) -> bool {
+        self.pawns + self.minors() + self.rooks + self.queens == 0
+    }
+}
+
+pub struct MaterialCount {
+    // The pieces of both sides, counted once for each evaluated position
+    white: Material,
+    black: Material,
+}
+
+impl MaterialCount {
+    pub fn new(board: &Board) -> MaterialCount {
+        MaterialCount {
+            white: Material::new(board, Color::White),
+            black: Material::new(board, Color::Black),
+        }
+    }
+}
+
+pub fn endgame_evaluation(board: &Board, material: &MaterialCount) -> Option<i32> {
+    // Returns an evaluation relative to the side to move for the recognised material signatures
+    // Returns None if the position has to be evaluated normally
+    if insufficient_material(board, material) {
+        return Some(0);
+    }
+    let (white, black) = (&material.white, &material.black);
+    let (strong, strong_material) = if black.is_bare_king() {
+        (Color::White, white)
+    } else if white.is_bare_king() {
+        (Color::Black, black)
+    } else {
+        return None;
+    };
+
+    let eval = if strong_material.pawns == 0
+        && strong_material.rooks == 0
+        && strong_material.queens == 0
+        && strong_material.knights == 1
+        && strong_material.bishops == 1
+    {
+        kbnk(board, strong)
+    } else if strong_material.rooks > 0 || strong_material.queens > 0 {
+        // KRK, KQK and everything with even more material
+        mate_bare_king(board, strong)
+            + 900 * strong_material.queens as i32
+            + 500 * strong_material.rooks as i32
+    } else if is_wrong_rook_pawn(board, strong, strong_material) {
+        0
+    } else {
+        return None;
+    };
+
+    if board.side_to_move() == strong {
+        Some(eval)
+    } else {
+        Some(-eval)
+    }
+}
+
+pub fn scale_factor(board: &Board, material: &MaterialCount) -> i32 {
+    // Returns how much of the evaluation should be kept in drawish endgames
+    let (white, black) = (&material.white, &material.black);
+    // Opposite colored bishops with pawns are hard to win even a few pawns up
+    if white.bishops == 1
+        && black.bishops == 1
+        && white.knights + white.rooks + white.queens == 0
+        && black.knights + black.rooks + black.queens == 0
+    {
+        let bishops = *board.pieces(Piece::Bishop);
+        if (bishops & LIGHT_SQUARES).popcnt() == 1 {
+            return SCALE_OPPOSITE_BISHOPS;
+        }
+    }
+    SCALE_NORMAL
+}
+
+pub fn is_insufficient_material(board: &Board) -> bool {
+    insufficient_material(board, &MaterialCount::new(board))
+}
+
+fn insufficient_material(board: &Board, material: &MaterialCount) -> bool {
+    // K vs K, minor piece vs K, KNN vs K and same colored bishops can't force a mate
+    let (white, black) = (&material.white, &material.black);
+    for side in [white, black] {
+        if side.pawns + side.rooks + side.queens != 0 {
+            return false;
+        }
+    }
+    if white.minors() + black.minors() <= 1 {
+        return true;
+    }
+    // Two knights can't mate a bare king
+    if (white.is_bare_king() && black.bishops == 0 && black.knights == 2)
+        || (black.is_bare_king() && white.bishops == 0 && white.knights == 2)
+    {
+        return true;
+    }
+    // Only bishops on the board and all of them on the same square color
+    let bishops = *board.pieces(Piece::Bishop);
+    white.knights + black.knights == 0
+        && ((bishops & LIGHT_SQUARES) == EMPTY || (bishops & !LIGHT_SQUARES) == EMPTY)
+}
+
+fn kbnk(board: &Board, strong: Color) -> i32 {
+    // The bare king has to be driven into a corner of the bishop's color
+    let bishop = (*board.pieces(Piece::Bishop) & board.color_combined(strong)).to_square();
+    let corners = if (BitBoard::from_square(bishop) & LIGHT_SQUARES) != EMPTY {
+        [Square::A8, Square::H1]
+    } else {
+        [Square::A1, Square::H8]
+    };
+    let weak_king = board.king_square(!strong);
+    let strong_king = board.king_square(strong);
+    let corner_distance = corners
+        .iter()
+        .map(|corner| manhattan_distance(weak_king, *corner))
+        .min()
+        .unwrap();
+    KNOWN_WIN + 20 * (14 - corner_distance) + 5 * (7 - distance(strong_king, weak_king))
+}
+
+fn mate_bare_king(board: &Board, strong: Color) -> i32 {
+    // The bare king has to be driven to the edge with the help of the other king
+    let weak_king = board.king_square(!strong);
+    let strong_king = board.king_square(strong);
+    KNOWN_WIN + 20 * center_distance(weak_king) + 5 * (7 - distance(strong_king, weak_king))
+}
+
+fn is_wrong_rook_pawn(board: &Board, strong: Color, material: &Material) -> bool {
+    // K + rook pawns (+ a bishop not controlling the promotion square) vs K
+    // is a draw when the defending king reaches the promotion corner
+    if material.pawns == 0 || material.knights + material.rooks + material.queens != 0 {
+        return false;
+    }
+    let pawns = *board.pieces(Piece::Pawn) & board.color_combined(strong);
+    let file = if (pawns & get_file(File::A)) == pawns {
+        File::A
+    } else if (pawns & get_file(File::H)) == pawns {
+        File::H
+    } else {
+        return false;
+    };
+    let promotion_rank = if strong == Color::White {
+        Rank::Eighth
+    } else {
+        Rank::First
+    };
+    let promotion = Square::make_square(promotion_rank, file);
+    let bishops = *board.pieces(Piece::Bishop) & board.color_combined(strong);
+    if bishops != EMPTY {
+        let promotion_light = (BitBoard::from_square(promotion) & LIGHT_SQUARES) != EMPTY;
+        let bishop_light = (bishops & LIGHT_SQUARES) != EMPTY;
+        if bishops.popcnt() > 1 || promotion_light == bishop_light {
+            return false;
+        }
+    }
+    distance(board.king_square(!strong), promotion) <= 1
+}
+
+fn distance(a: Square, b: Square) -> i32 {
+    // Number of king moves between two squares
+    let files = (a.get_file().to_index() as i32 - b.get_file().to_index() as i32).abs();
+    let ranks = (a.get_rank().to_index() as i32 - b.get_rank().to_index() as i32).abs();
+    files.max(ranks)
+}
+
+fn manhattan_distance(a: Square, b: Square) -> i32 {
+    let files = (a.get_file().to_index() as i32 - b.get_file().to_index() as i32).abs();
+    let ranks = (a.get_rank().to_index() as i32 - b.get_rank().to_index() as i32).abs();
+    files + ranks
+}
+
+fn center_distance(square: Square) -> i32 {
+    // 0 for the central squares, 6 for the corners
+    let file = square.get_file().to_index() as i32;
+    let rank = square.get_rank().to_index() as i32;
+    (3 - file).max(file - 4) + (3 - rank).max(rank - 4)
+}
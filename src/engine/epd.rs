@@ -0,0 +1,88 @@
+use super::fen::Fen;
+use super::notation::parse_san;
+use chess::{Board, ChessMove};
+
+#[derive(Clone, Debug)]
+pub struct EpdRecord {
+    // One line of an EPD file: a position followed by operations like
+    //     bm Qd1+ Nf3; id "WAC.001";
+    pub board: Board,
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl EpdRecord {
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")
+            .and_then(|operands| operands.first())
+            .map(|id| id.as_str())
+    }
+
+    pub fn moves(&self, opcode: &str) -> Vec<ChessMove> {
+        // Reads the moves of a bm or am operation, SAN moves which are not legal are left out
+        self.operation(opcode).map_or(Vec::new(), |operands| {
+            operands
+                .iter()
+                .filter_map(|san| parse_san(&self.board, san))
+                .collect()
+        })
+    }
+}
+
+pub fn parse_epd(line: &str) -> Option<EpdRecord> {
+    // EPD positions have only the first four FEN fields, the move counters are optional
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 4 {
+        return None;
+    }
+    let mut rest = fields[4..].to_vec();
+    let counters = rest
+        .iter()
+        .take(2)
+        .take_while(|x| x.parse::<u32>().is_ok())
+        .count();
+    let counters = if counters == 2 { 2 } else { 0 };
+    rest.drain(..counters);
+    let board = Fen::parse(&fields[..4 + counters].join(" ")).ok()?.board;
+    Some(EpdRecord {
+        board,
+        operations: parse_operations(&rest.join(" ")),
+    })
+}
+
+pub fn parse_operations(text: &str) -> Vec<(String, Vec<String>)> {
+    // Operations end with a semicolon, operands in quotes may contain spaces and semicolons
+    let mut operations = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' | ' ' | '\t' if !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                if c == ';' && !words.is_empty() {
+                    let opcode = words.remove(0);
+                    operations.push((opcode, std::mem::take(&mut words)));
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    if !words.is_empty() {
+        let opcode = words.remove(0);
+        operations.push((opcode, words));
+    }
+    operations
+}
@@ -1,8 +1,10 @@
+use super::endgame::*;
 use super::helpers::is_endgame;
-use super::tables::*;
+use super::weights::Weights;
 use chess::{
-    get_bishop_moves, get_knight_moves, get_pawn_attacks, get_rook_moves, BitBoard, Board,
-    BoardStatus, Color, File, Piece, Rank, Square, ALL_COLORS, EMPTY,
+    get_adjacent_files, get_bishop_moves, get_file, get_knight_moves, get_pawn_attacks, get_rank,
+    get_rook_moves, BitBoard, Board, BoardStatus, CastleRights, Color, File, Piece, Rank, Square,
+    ALL_COLORS, ALL_PIECES, EMPTY,
 };
 use once_cell::sync::Lazy;
 
@@ -13,36 +15,340 @@ static CENTER: Lazy<BitBoard> = Lazy::new(|| {
         & BitBoard::from_square(Square::E5)
 });
 
-pub fn evaluation(board: &Board, alpha: i32, beta: i32) -> i32 {
+// Squares of the same color as h1
+pub(super) const LIGHT_SQUARES: BitBoard = BitBoard(0x55AA_55AA_55AA_55AA);
+
+pub trait Evaluator {
+    // Evaluation backend used by the search
+    // The search reports every move it makes and takes back so the backends can update their state incrementally
+
+    // Called with the root position before every search
+    fn reset(&mut self, _board: &Board) {}
+
+    // Called when the search moves from one position to the next one
+    fn make_move(&mut self, _before: &Board, _after: &Board) {}
+
+    // Called when the search returns to the previous position
+    fn unmake_move(&mut self) {}
+
+    // Returns an evaluation relative to the side to move
+    fn evaluate(&mut self, board: &Board, alpha: i32, beta: i32) -> i32;
+
+    // Replaces the weights of the handcrafted evaluation, ignored by the other backends
+    fn set_weights(&mut self, _weights: &Weights) {}
+}
+
+pub struct Classical {
+    pub weights: Weights,
+    // Material and placement of the pieces other than the kings from white's point of view,
+    // one value for every position on the current search path, only the first ply + 1 are valid
+    stack: Vec<i32>,
+    ply: usize,
+}
+
+impl Default for Classical {
+    fn default() -> Self {
+        Self::new(Weights::default())
+    }
+}
+
+impl Classical {
+    pub fn new(weights: Weights) -> Classical {
+        Classical {
+            weights,
+            stack: vec![0],
+            ply: 0,
+        }
+    }
+}
+
+impl Evaluator for Classical {
+    fn reset(&mut self, board: &Board) {
+        self.ply = 0;
+        self.stack[0] = material(board, &self.weights);
+    }
+
+    fn make_move(&mut self, before: &Board, after: &Board) {
+        // Only the squares whose piece changed are updated, which covers captures, castling,
+        // en passant and promotions alike
+        let mut material = self.stack[self.ply];
+        for color in ALL_COLORS {
+            for piece in ALL_PIECES {
+                let old = *before.pieces(piece) & before.color_combined(color);
+                let new = *after.pieces(piece) & after.color_combined(color);
+                for square in old & !new {
+                    material -= piece_square_value(piece, square, color, &self.weights);
+                }
+                for square in new & !old {
+                    material += piece_square_value(piece, square, color, &self.weights);
+                }
+            }
+        }
+        self.ply += 1;
+        if self.stack.len() == self.ply {
+            self.stack.push(material);
+        } else {
+            self.stack[self.ply] = material;
+        }
+    }
+
+    fn unmake_move(&mut self) {
+        self.ply = self.ply.saturating_sub(1);
+    }
+
+    fn evaluate(&mut self, board: &Board, alpha: i32, beta: i32) -> i32 {
+        let material = self.stack[self.ply];
+        debug_assert_eq!(
+            material,
+            super::evaluation::material(board, &self.weights),
+            "incremental material of {}",
+            board
+        );
+        evaluate_with_material(board, alpha, beta, &self.weights, material)
+    }
+
+    fn set_weights(&mut self, weights: &Weights) {
+        // The stack is built again with the new weights by the next reset
+        self.weights = weights.clone();
+    }
+}
+
+pub fn evaluation(board: &Board, alpha: i32, beta: i32, weights: &Weights) -> i32 {
     // Evaluation function which returns an evaluation relative to the side to move
     // Returns a value if the game has ended
+    evaluate_with_material(board, alpha, beta, weights, material(board, weights))
+}
+
+pub fn material(board: &Board, weights: &Weights) -> i32 {
+    // Material and placement of all the pieces except the kings from white's point of view
+    let mut eval = 0;
+    for color in ALL_COLORS {
+        for piece in ALL_PIECES {
+            for square in *board.pieces(piece) & board.color_combined(color) {
+                eval += piece_square_value(piece, square, color, weights);
+            }
+        }
+    }
+    eval
+}
+
+fn evaluate_with_material(
+    board: &Board,
+    alpha: i32,
+    beta: i32,
+    weights: &Weights,
+    material: i32,
+) -> i32 {
+    // The evaluation given the material and placement of the pieces other than the kings
     match board.status() {
         BoardStatus::Checkmate => return -99_999,
         BoardStatus::Stalemate => return 0,
         BoardStatus::Ongoing => (),
     }
-    let mut eval = 0;
-    // Iterates over all the pieces on the board
-    for color in ALL_COLORS {
-        let pieces = *board.color_combined(color);
-        for square in pieces {
-            eval += evaluate_piece(board, square, color, board.side_to_move())
-        }
+    // Known endgames override the general evaluation
+    let material_count = MaterialCount::new(board);
+    if let Some(eval) = endgame_evaluation(board, &material_count) {
+        return eval;
     }
+    let turn_multiplier = if board.side_to_move() == Color::White {
+        1
+    } else {
+        -1
+    };
+    // The king tables depend on the material left on the board so the kings aren't kept incrementally
+    let endgame = is_endgame(board);
+    let mut eval = (material + king_value(board, Color::White, endgame, weights)
+        - king_value(board, Color::Black, endgame, weights))
+        * turn_multiplier;
+    // The bishop pair is treated as a part of the material so it is not left to the lazy evaluation
+    eval += (bishop_pair(board, Color::White, weights) - bishop_pair(board, Color::Black, weights))
+        * turn_multiplier;
 
     // Lazy evaluation
     // Executes only if the position is promising
-    // 80 is the 'max' player gets in a material equal position + some little margin
-    let margin = 80;
+    // The margin is the 'max' player gets in a material equal position + some little margin
+    let margin = weights.lazy_margin;
     if eval + margin < beta && eval + margin > alpha {
         // center can be used only with cache, it would be to slow otherwise
-        eval += mobility(board, Color::White) + center_control(board, Color::White);
-        eval -= mobility(board, Color::Black) + center_control(board, Color::Black);
+        eval += (mobility(board, Color::White, weights)
+            + center_control(board, Color::White, weights)
+            - mobility(board, Color::Black, weights)
+            - center_control(board, Color::Black, weights)
+            + piece_terms(board, Color::White, weights)
+            - piece_terms(board, Color::Black, weights))
+            * turn_multiplier;
+    }
+    eval * scale_factor(board, &material_count) / SCALE_NORMAL
+}
+
+pub fn piece_terms(board: &Board, color: Color, weights: &Weights) -> i32 {
+    // Sum of the piece-specific positional terms relative to the selected side
+    rook_files(board, color, weights)
+        + rook_seventh_rank(board, color, weights)
+        + outposts(board, color, weights)
+        - bad_bishop(board, color, weights)
+        - trapped_pieces(board, color, weights)
+}
+
+pub fn bishop_pair(board: &Board, color: Color, weights: &Weights) -> i32 {
+    // Two bishops complement each other as they cover both square colors
+    let bishops = *board.pieces(Piece::Bishop) & board.color_combined(color);
+    if (bishops & LIGHT_SQUARES) != EMPTY && (bishops & !LIGHT_SQUARES) != EMPTY {
+        weights.bishop_pair
+    } else {
+        0
+    }
+}
+
+pub fn rook_files(board: &Board, color: Color, weights: &Weights) -> i32 {
+    // Rewards rooks on files without own pawns, more so if there are no pawns at all
+    let mut eval = 0;
+    let pawns = *board.pieces(Piece::Pawn);
+    let own_pawns = pawns & board.color_combined(color);
+    for rook in *board.pieces(Piece::Rook) & board.color_combined(color) {
+        let file = get_file(rook.get_file());
+        if (file & pawns) == EMPTY {
+            eval += weights.rook_open_file;
+        } else if (file & own_pawns) == EMPTY {
+            eval += weights.rook_half_open_file;
+        }
+    }
+    eval
+}
+
+pub fn rook_seventh_rank(board: &Board, color: Color, weights: &Weights) -> i32 {
+    // A rook on the 7th rank is strong only if it attacks pawns there or cuts off the king on the 8th rank
+    let seventh = get_rank(relative_rank(Rank::Seventh, color));
+    let eighth = get_rank(relative_rank(Rank::Eighth, color));
+    let enemy_pawns = *board.pieces(Piece::Pawn) & board.color_combined(!color);
+    let enemy_king = *board.pieces(Piece::King) & board.color_combined(!color);
+    if (enemy_pawns & seventh) == EMPTY && (enemy_king & eighth) == EMPTY {
+        return 0;
+    }
+    let rooks = *board.pieces(Piece::Rook) & board.color_combined(color);
+    (rooks & seventh).popcnt() as i32 * weights.rook_seventh_rank
+}
+
+pub fn outposts(board: &Board, color: Color, weights: &Weights) -> i32 {
+    // A knight or a bishop on the 4th to 6th rank which is protected by a pawn and can't be chased away by enemy pawns
+    let mut eval = 0;
+    let own_pawns = *board.pieces(Piece::Pawn) & board.color_combined(color);
+    let enemy_pawns = *board.pieces(Piece::Pawn) & board.color_combined(!color);
+    let minors =
+        (*board.pieces(Piece::Knight) | *board.pieces(Piece::Bishop)) & board.color_combined(color);
+    for square in minors {
+        let rank = relative_rank(square.get_rank(), color).to_index();
+        if !(3..=5).contains(&rank) {
+            continue;
+        }
+        // Squares from which an own pawn protects the piece
+        if (get_pawn_attacks(square, !color, own_pawns)) == EMPTY {
+            continue;
+        }
+        // Enemy pawns on the adjacent files in front of the piece could still attack it
+        if (get_adjacent_files(square.get_file()) & ranks_ahead(square, color) & enemy_pawns)
+            != EMPTY
+        {
+            continue;
+        }
+        eval += if board.piece_on(square) == Some(Piece::Knight) {
+            weights.knight_outpost
+        } else {
+            weights.bishop_outpost
+        };
+    }
+    eval
+}
+
+pub fn bad_bishop(board: &Board, color: Color, weights: &Weights) -> i32 {
+    // Penalty for a bishop hemmed in by its own pawns standing on the squares of its color
+    let mut eval = 0;
+    let own_pawns = *board.pieces(Piece::Pawn) & board.color_combined(color);
+    for bishop in *board.pieces(Piece::Bishop) & board.color_combined(color) {
+        let squares = if (BitBoard::from_square(bishop) & LIGHT_SQUARES) != EMPTY {
+            LIGHT_SQUARES
+        } else {
+            !LIGHT_SQUARES
+        };
+        eval += (own_pawns & squares).popcnt() as i32 * weights.bad_bishop;
     }
     eval
 }
 
-fn center_control(board: &Board, color: Color) -> i32 {
+pub fn trapped_pieces(board: &Board, color: Color, weights: &Weights) -> i32 {
+    // Penalty for the well known patterns where a piece can't get out
+    // Bishop on a7/h7 (a6/h6) cut off by an enemy pawn on b6/g6 (b5/g5)
+    // Rook in the corner blocked by its own uncastled king
+    let mut eval = 0;
+    let bishops = *board.pieces(Piece::Bishop) & board.color_combined(color);
+    let enemy_pawns = *board.pieces(Piece::Pawn) & board.color_combined(!color);
+    let patterns = [
+        (Square::A7, Square::B6, weights.trapped_bishop),
+        (Square::H7, Square::G6, weights.trapped_bishop),
+        (Square::A6, Square::B5, weights.trapped_bishop / 2),
+        (Square::H6, Square::G5, weights.trapped_bishop / 2),
+    ];
+    for (bishop, pawn, penalty) in patterns {
+        if (bishops & BitBoard::from_square(relative_square(bishop, color))) != EMPTY
+            && (enemy_pawns & BitBoard::from_square(relative_square(pawn, color))) != EMPTY
+        {
+            eval += penalty;
+        }
+    }
+
+    if board.castle_rights(color) != CastleRights::NoRights {
+        return eval;
+    }
+    let rooks = *board.pieces(Piece::Rook) & board.color_combined(color);
+    let king = board.king_square(color);
+    let king_side = [Square::F1, Square::G1].map(|sq| relative_square(sq, color));
+    let queen_side = [Square::B1, Square::C1, Square::D1].map(|sq| relative_square(sq, color));
+    let corner = if king_side.contains(&king) {
+        [Square::G1, Square::H1, Square::H2]
+    } else if queen_side.contains(&king) {
+        [Square::A1, Square::B1, Square::A2]
+    } else {
+        return eval;
+    };
+    for square in corner {
+        let square = relative_square(square, color);
+        if (rooks & BitBoard::from_square(square)) != EMPTY
+            && square.get_file().to_index().abs_diff(File::E.to_index())
+                > king.get_file().to_index().abs_diff(File::E.to_index())
+        {
+            eval += weights.trapped_rook;
+        }
+    }
+    eval
+}
+
+fn relative_rank(rank: Rank, color: Color) -> Rank {
+    // Rank seen from the side of the given color
+    if color == Color::White {
+        rank
+    } else {
+        Rank::from_index(7 - rank.to_index())
+    }
+}
+
+fn relative_square(square: Square, color: Color) -> Square {
+    // Square given from white's point of view mirrored for black
+    Square::make_square(relative_rank(square.get_rank(), color), square.get_file())
+}
+
+fn ranks_ahead(square: Square, color: Color) -> BitBoard {
+    // All the ranks in front of the square from the side of the given color
+    let mut mask = EMPTY;
+    let rank = square.get_rank().to_index();
+    for index in 0..8 {
+        if (color == Color::White && index > rank) || (color == Color::Black && index < rank) {
+            mask |= get_rank(Rank::from_index(index));
+        }
+    }
+    mask
+}
+
+fn center_control(board: &Board, color: Color, weights: &Weights) -> i32 {
     // Checks if pieces control the center
     // Not implemented for the king and the pawns
     // King shouldn't be encouraged to be in the center
@@ -58,7 +364,7 @@ fn center_control(board: &Board, color: Color) -> i32 {
         if board.color_on(piece).unwrap() == color {
             // Checks the bishop moves
             if (get_bishop_moves(piece, *pieces) & *CENTER) != EMPTY {
-                eval += 5;
+                eval += weights.center_control;
             }
         }
     }
@@ -67,7 +373,7 @@ fn center_control(board: &Board, color: Color) -> i32 {
         if board.color_on(piece).unwrap() == color {
             // Checks the knight moves
             if (get_knight_moves(piece) & *CENTER) != EMPTY {
-                eval += 5;
+                eval += weights.center_control;
             }
         }
     }
@@ -76,7 +382,7 @@ fn center_control(board: &Board, color: Color) -> i32 {
         if board.color_on(piece).unwrap() == color {
             // Checks the rook moves
             if (get_rook_moves(piece, *pieces) & *CENTER) != EMPTY {
-                eval += 5;
+                eval += weights.center_control;
             }
         }
     }
@@ -87,14 +393,14 @@ fn center_control(board: &Board, color: Color) -> i32 {
             if (get_rook_moves(piece, *pieces) & *CENTER) != EMPTY
                 || (get_bishop_moves(piece, *pieces) & *CENTER) != EMPTY
             {
-                eval += 5;
+                eval += weights.center_control;
             }
         }
     }
     eval
 }
 
-fn mobility(board: &Board, color: Color) -> i32 {
+fn mobility(board: &Board, color: Color, weights: &Weights) -> i32 {
     // Returns evaluation of the mobility relative to the selected side
     let mut eval = 0;
     let board_clone: Board;
@@ -102,7 +408,7 @@ fn mobility(board: &Board, color: Color) -> i32 {
     // Makes a null move if it's not the turn of the selected color
     // The board needs to be cloned because the crate erases en passants when doing a null move
     let board = if color != board.side_to_move() {
-        board_clone = board.clone();
+        board_clone = *board;
         board_clone.null_move();
         &board_clone
     } else {
@@ -116,59 +422,61 @@ fn mobility(board: &Board, color: Color) -> i32 {
     let queens = *board.pieces(Piece::Queen) & board.color_combined(color);
     let enemy_pawns = *board.pieces(Piece::Pawn) & board.color_combined(!color);
     // Finds the intersection of bishop moves and opponents pawn attacks and then counts only the squares that are unprotected by a pawn
-    // The reward for possible squares is as follows:
+    // The reward for possible squares is given by the weights, by default:
     // Bishop and Rooks: 3 centipawns
     // Queen: 1 centipawn (She has the biggest mobility and her mobility is not that important)
     // Knight: 5 centipawns (It has the least mobility and its mobility is very important)
     let mut attacks_on_bishop = EMPTY;
     for pawn in enemy_pawns {
-        attacks_on_bishop = attacks_on_bishop & get_pawn_attacks(pawn, !color, bishops)
+        attacks_on_bishop &= get_pawn_attacks(pawn, !color, bishops)
     }
-    eval += (bishops ^ attacks_on_bishop).collect::<Vec<_>>().len() * 3;
+    eval += (bishops ^ attacks_on_bishop).popcnt() as i32 * weights.bishop_mobility;
 
     let mut attacks_on_knight = EMPTY;
     for pawn in enemy_pawns {
-        attacks_on_knight = attacks_on_knight & get_pawn_attacks(pawn, !color, knights)
+        attacks_on_knight &= get_pawn_attacks(pawn, !color, knights)
     }
-    eval += (knights ^ attacks_on_knight).collect::<Vec<_>>().len() * 5;
+    eval += (knights ^ attacks_on_knight).popcnt() as i32 * weights.knight_mobility;
 
     let mut attacks_on_rook = EMPTY;
     for pawn in enemy_pawns {
-        attacks_on_rook = attacks_on_rook & get_pawn_attacks(pawn, !color, rooks);
+        attacks_on_rook &= get_pawn_attacks(pawn, !color, rooks);
     }
-    eval += (rooks ^ attacks_on_rook).collect::<Vec<_>>().len() * 3;
+    eval += (rooks ^ attacks_on_rook).popcnt() as i32 * weights.rook_mobility;
 
     let mut attacks_on_queen = EMPTY;
     for pawn in enemy_pawns {
-        attacks_on_queen = attacks_on_queen & get_pawn_attacks(pawn, !color, queens);
+        attacks_on_queen &= get_pawn_attacks(pawn, !color, queens);
     }
-    eval += (queens ^ attacks_on_queen).collect::<Vec<_>>().len();
+    eval += (queens ^ attacks_on_queen).popcnt() as i32 * weights.queen_mobility;
 
-    eval as i32
+    eval
 }
 
-fn evaluate_piece(board: &Board, square: Square, color: Color, turn: Color) -> i32 {
+fn piece_square_value(piece: Piece, square: Square, color: Color, weights: &Weights) -> i32 {
     // Returns the material value of a piece and its value according to a placement table
-    let turn_multiplier = if turn == Color::White { 1 } else { -1 };
+    // The value is from white's point of view, kings are left to king_value
     let color_multiplier = if color == Color::White { 1 } else { -1 };
-    let piece = board.piece_on(square).unwrap();
     (match piece {
         // Higher pawn value because pawns don't have mobility so their actual value would be lower relative to other pieces
-        // Read table is called only on the pieces that require it
-        Piece::Pawn => 100 + read_table(&PAWN_TABLE, square, color),
-        Piece::Knight => 300 + KNIGHT_TABLE[square.to_int() as usize],
-        Piece::Bishop => 310 + BISHOP_TABLE[square.to_int() as usize],
-        Piece::Rook => 500 + read_table(&ROOK_TABLE, square, color),
-        Piece::Queen => 900 + QUEEN_TABLE[square.to_int() as usize],
-        Piece::King => {
-            if is_endgame(board) {
-                KING_TABLE_ENDGAME[square.to_int() as usize]
-            } else {
-                read_table(&KING_TABLE, square, color)
-            }
-        }
-    } * turn_multiplier
-        * color_multiplier)
+        // The tables are written from white's side with the 8th rank first, read_table mirrors them for white
+        Piece::Pawn => weights.pawn_value + read_table(&weights.pawn_table, square, color),
+        Piece::Knight => weights.knight_value + read_table(&weights.knight_table, square, color),
+        Piece::Bishop => weights.bishop_value + read_table(&weights.bishop_table, square, color),
+        Piece::Rook => weights.rook_value + read_table(&weights.rook_table, square, color),
+        Piece::Queen => weights.queen_value + read_table(&weights.queen_table, square, color),
+        Piece::King => 0,
+    }) * color_multiplier
+}
+
+fn king_value(board: &Board, color: Color, endgame: bool, weights: &Weights) -> i32 {
+    // Placement of the king, it should stay in its shelter until the endgame
+    let square = board.king_square(color);
+    if endgame {
+        read_table(&weights.king_table_endgame, square, color)
+    } else {
+        read_table(&weights.king_table, square, color)
+    }
 }
 
 fn read_table(table: &[i32; 64], square: Square, color: Color) -> i32 {
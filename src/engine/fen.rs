@@ -0,0 +1,230 @@
+use super::notation::{file_char, piece_char, rank_char};
+use chess::{Board, CastleRights, Color, File, Rank, Square};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CastlingNotation {
+    // KQkq, which X-FEN also uses as long as the castling rook is the outermost one
+    Standard,
+    // The file of the castling rook, uppercase for white, e.g. HAha
+    Shredder,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fen {
+    // A position together with the move counters, which Board doesn't keep
+    pub board: Board,
+    // The square behind a pawn which just moved two squares, as written in the FEN
+    // Board only keeps it when a pawn can take en passant
+    pub en_passant: Option<Square>,
+    pub halfmove: u32,
+    pub fullmove: u32,
+}
+
+impl Fen {
+    pub fn new(board: Board, halfmove: u32, fullmove: u32) -> Fen {
+        // Board keeps the square of the pawn which can be taken, FEN has the square behind it
+        let en_passant = board
+            .en_passant()
+            .map(|square| square.uforward(board.side_to_move()));
+        Fen {
+            board,
+            en_passant,
+            halfmove,
+            fullmove,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Fen, String> {
+        // Reads a FEN with KQkq, X-FEN or Shredder-FEN castling rights
+        // The move counters are optional so EPD positions can be read too
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(format!("expected 4 to 6 fields, found {}", fields.len()));
+        }
+        if !matches!(fields[1], "w" | "b") {
+            return Err(format!("invalid side to move: {}", fields[1]));
+        }
+        if fields[3] != "-" && !is_en_passant_square(fields[3], fields[1]) {
+            return Err(format!("invalid en passant square: {}", fields[3]));
+        }
+        let halfmove = match fields.get(4) {
+            Some(x) => x
+                .parse::<u32>()
+                .map_err(|_| format!("invalid halfmove clock: {}", x))?,
+            None => 0,
+        };
+        // Some GUIs and older versions of this engine write 0 as the fullmove number, it is read as 1
+        let fullmove = match fields.get(5) {
+            Some(x) => x
+                .parse::<u32>()
+                .map_err(|_| format!("invalid fullmove number: {}", x))?
+                .max(1),
+            None => 1,
+        };
+
+        // The castling letters are converted to KQkq using the kings' files
+        let position = format!("{} {} - - 0 1", fields[0], fields[1]);
+        let uncastled = Board::from_str(&position).map_err(|e| e.to_string())?;
+        let castling = standard_castling(&uncastled, fields[2])?;
+        let fen = format!("{} {} {} {} 0 1", fields[0], fields[1], castling, fields[3]);
+        let board = Board::from_str(&fen).map_err(|e| e.to_string())?;
+        Ok(Fen {
+            board,
+            en_passant: Square::from_str(fields[3]).ok(),
+            halfmove,
+            fullmove,
+        })
+    }
+
+    pub fn to_fen(&self, notation: CastlingNotation) -> String {
+        let board = &self.board;
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                let square = Square::make_square(Rank::from_index(rank), File::from_index(file));
+                match (board.piece_on(square), board.color_on(square)) {
+                    (Some(piece), Some(color)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        let c = piece_char(piece);
+                        fen.push(if color == Color::White {
+                            c
+                        } else {
+                            c.to_ascii_lowercase()
+                        });
+                    }
+                    _ => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match board.side_to_move() {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        fen.push(' ');
+        let mut castling = String::new();
+        for color in [Color::White, Color::Black] {
+            let rights = board.castle_rights(color);
+            let (king_side, queen_side) = match notation {
+                CastlingNotation::Standard => ('K', 'Q'),
+                CastlingNotation::Shredder => ('H', 'A'),
+            };
+            let mut push = |c: char| {
+                castling.push(if color == Color::White {
+                    c
+                } else {
+                    c.to_ascii_lowercase()
+                })
+            };
+            if rights.has_kingside() {
+                push(king_side);
+            }
+            if rights.has_queenside() {
+                push(queen_side);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        fen.push_str(&castling);
+
+        fen.push(' ');
+        match self.en_passant {
+            Some(square) => {
+                fen.push(file_char(square.get_file()));
+                fen.push(rank_char(square.get_rank()));
+            }
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(" {} {}", self.halfmove, self.fullmove));
+        fen
+    }
+}
+
+impl fmt::Display for Fen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen(CastlingNotation::Standard))
+    }
+}
+
+impl FromStr for Fen {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Fen, String> {
+        Fen::parse(text)
+    }
+}
+
+pub fn board_to_fen(board: &Board, halfmove: u32, fullmove: u32) -> String {
+    Fen::new(*board, halfmove, fullmove).to_string()
+}
+
+fn is_en_passant_square(square: &str, side: &str) -> bool {
+    // The square a pawn passed over, on the 6th rank if white is to move and the 3rd otherwise
+    let mut chars = square.chars();
+    let rank = if side == "w" { '6' } else { '3' };
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some('a'..='h'), Some(r), None) if r == rank
+    )
+}
+
+fn standard_castling(board: &Board, castling: &str) -> Result<String, String> {
+    if castling == "-" {
+        return Ok(castling.to_string());
+    }
+    let mut rights = [CastleRights::NoRights; 2];
+    for c in castling.chars() {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let king_file = board.king_square(color).get_file().to_index();
+        let right = match c.to_ascii_uppercase() {
+            'K' => CastleRights::KingSide,
+            'Q' => CastleRights::QueenSide,
+            // Shredder-FEN names the file of the rook, only the corner rooks can castle
+            'A' if king_file == 4 => CastleRights::QueenSide,
+            'H' if king_file == 4 => CastleRights::KingSide,
+            _ => return Err(format!("invalid castling rights: {}", castling)),
+        };
+        let index = color.to_index();
+        if rights[index].add(right) == rights[index] {
+            return Err(format!("invalid castling rights: {}", castling));
+        }
+        rights[index] = rights[index].add(right);
+    }
+
+    let mut standard = String::new();
+    for color in [Color::White, Color::Black] {
+        let rights = rights[color.to_index()];
+        let mut letters = String::new();
+        if rights.has_kingside() {
+            letters.push('K');
+        }
+        if rights.has_queenside() {
+            letters.push('Q');
+        }
+        if color == Color::Black {
+            letters = letters.to_ascii_lowercase();
+        }
+        standard.push_str(&letters);
+    }
+    Ok(standard)
+}
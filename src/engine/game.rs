@@ -1,39 +1,299 @@
+use super::book::Book;
+use super::chess960::{CastlingRooks, Chess960};
+use super::endgame::is_insufficient_material;
+use super::fen::Fen;
+use super::notation::{parse_move, to_san};
+use super::pgn::{PgnGame, STARTING_FEN};
 use super::search::Engine;
-use chess::{Board, ChessMove, File, Piece, Rank, Square};
-use std::str::FromStr;
-use std::time::Instant;
+use super::weights::Weights;
+use chess::{Board, BoardStatus, ChessMove, Color, Piece};
+use std::io;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug)]
+pub struct MoveRecord {
+    // One move of the game together with the position it was played in
+    // and the search which selected it
+    pub board: Board,
+    pub chess_move: ChessMove,
+    pub san: String,
+    // Score relative to the side which played the move and the search depth
+    // None for moves which weren't searched by the engine (opponent's or book moves)
+    pub score: Option<i32>,
+    pub depth: Option<i32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    // Result of analysing one position
+    pub best_move: ChessMove,
+    // Score relative to the side to move
+    pub score: i32,
+    pub depth: i32,
+    // Expected continuation starting with the best move
+    pub line: Vec<ChessMove>,
+}
 
 pub struct Game {
     // A game structure that holds all the data together
     pub board: Board,
     ai: Engine,
     depth: Option<i32>,
+    book: Option<Book>,
+    own_book: bool,
+    book_depth: u32,
+    // Number of plies played since the start of the game
+    ply: u32,
+    // Position the game started from and the moves played since
+    start: Fen,
+    history: Vec<MoveRecord>,
+    // Moves which were taken back, the last one is replayed first by redo
+    redo: Vec<MoveRecord>,
+    // Result of the last search: position hash, move, score and depth
+    // It is attached to the move when the move is played
+    last_search: Option<(u64, ChessMove, i32, i32)>,
+    // Chess960 games keep their starting and current position with the castling rights
+    chess960: bool,
+    start960: Option<Chess960>,
+    position960: Option<Chess960>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Game {
+        Game::with_engine(Engine::new())
+    }
+
+    pub fn with_cache_size(size: usize) -> Game {
+        // Creates a game whose engine has a smaller or bigger hash, the size has to be a power of two
+        Game::with_engine(Engine::with_cache_size(size))
+    }
+
+    fn with_engine(ai: Engine) -> Game {
         Game {
             board: Board::default(),
-            ai: Engine::new(),
+            ai,
             depth: None,
+            book: None,
+            own_book: false,
+            book_depth: 20,
+            ply: 0,
+            start: Fen::parse(STARTING_FEN).unwrap(),
+            history: Vec::new(),
+            redo: Vec::new(),
+            last_search: None,
+            chess960: false,
+            start960: None,
+            position960: None,
         }
     }
 
+    pub fn reset(&mut self) {
+        // Starts a new game from the standard position
+        // The engine and the options stay loaded, only the hash of the previous game is cleared
+        self.ai.clear_hash();
+        self.board = Board::default();
+        self.ply = 0;
+        self.start = Fen::parse(STARTING_FEN).unwrap();
+        self.history.clear();
+        self.redo.clear();
+        self.last_search = None;
+        self.start960 = None;
+        if self.chess960 {
+            self.start960 = Chess960::from_fen(STARTING_FEN).ok();
+        }
+        self.position960 = self.start960.clone();
+    }
+
     pub fn set_depth(&mut self, depth: i32) {
         // sets the depth to a given value
         self.depth = Some(depth);
     }
 
+    pub fn set_syzygy_path(&mut self, path: &str) -> io::Result<usize> {
+        // Loads the Syzygy tablebases used by the engine
+        self.ai.set_syzygy_path(path)
+    }
+
+    pub fn set_eval_file(&mut self, path: &str) -> io::Result<()> {
+        // Loads the NNUE network used by the engine
+        self.ai.set_eval_file(path)
+    }
+
+    pub fn set_eval_params(&mut self, path: &str) -> Result<(), String> {
+        // Loads the weights of the classical evaluation from a TOML file
+        // An empty path restores the compiled-in weights
+        if path.is_empty() || path == "<empty>" {
+            self.ai.set_weights(Weights::default());
+            return Ok(());
+        }
+        self.ai.set_weights(Weights::load(path)?);
+        Ok(())
+    }
+
+    pub fn set_tunable(&mut self, name: &str, value: i32) -> bool {
+        // Changes a search or evaluation constant, returns false if the name is unknown
+        self.ai.set_tunable(name, value)
+    }
+
+    pub fn tunable(&self, name: &str) -> Option<i32> {
+        // Current value of a search or evaluation constant, None if the name is unknown
+        self.ai.tunable(name)
+    }
+
+    pub fn set_book_file(&mut self, path: &str) -> io::Result<usize> {
+        // Loads a Polyglot opening book, an empty path unloads the current one
+        // Returns the number of entries in the book
+        if path.is_empty() || path == "<empty>" {
+            self.book = None;
+            return Ok(0);
+        }
+        let book = Book::open(path)?;
+        let entries = book.len();
+        self.book = Some(book);
+        Ok(entries)
+    }
+
+    pub fn set_own_book(&mut self, own_book: bool) {
+        // Enables or disables playing the moves from the opening book
+        self.own_book = own_book;
+    }
+
+    pub fn set_book_depth(&mut self, book_depth: u32) {
+        // Sets the number of plies from the start of the game in which the book is used
+        self.book_depth = book_depth;
+    }
+
+    pub fn set_chess960(&mut self, chess960: bool) {
+        // Switches between standard chess and Chess960 and starts a new game
+        // In Chess960 castling moves are written as the king taking its own rook
+        self.chess960 = chess960;
+        self.reset();
+    }
+
+    pub fn book_move(&self) -> Option<ChessMove> {
+        // Returns a move from the opening book if the book should be used in the position
+        // The Polyglot books only cover standard chess
+        if !self.own_book || self.chess960 || self.ply >= self.book_depth {
+            return None;
+        }
+        self.book.as_ref()?.pick(&self.board)
+    }
+
     pub fn play(&mut self) -> ChessMove {
         // returns the best move in the position according to the engine
         // If no depth was selected before, it uses the depth 3
+        // The opening book is tried before the search
 
+        if let Some(book_move) = self.book_move() {
+            return book_move;
+        }
         let depth = self.depth.unwrap_or(4);
-        let best_move = self.ai.play(&self.board, depth);
-        if best_move == None {
-            panic!("Internal error, Invalid position")
+        let (best_move, score) = self
+            .root_search(depth)
+            .expect("Internal error, Invalid position");
+        self.last_search = Some((self.board.get_hash(), best_move, score, depth));
+        best_move
+    }
+
+    pub fn analyse(&mut self, time: Duration, max_depth: i32) -> Option<Analysis> {
+        // Deepens the search of the current position until the time has passed or max_depth is reached
+        // The search is stopped inside the tree, only the first iteration always finishes
+        // Returns None if the game has ended
+        // A time too long to be added to the clock, like Duration::MAX, searches to max_depth
+        let deadline = Instant::now().checked_add(time);
+        let mut result = None;
+        for depth in 1..=max_depth {
+            match self.root_search(depth) {
+                Some((best_move, score)) => result = Some((best_move, score, depth)),
+                None => break,
+            }
+            self.ai.set_deadline(deadline);
+        }
+        self.ai.set_deadline(None);
+        let (best_move, score, depth) = result?;
+
+        // The search doesn't keep the principal variation, the line is built
+        // by searching the following positions with decreasing depth
+        let mut line = vec![best_move];
+        let mut board = self.board_after(best_move);
+        let mut castling = self.castling_rooks().after(&self.board, best_move);
+        for reply_depth in (1..depth).rev() {
+            if board.status() != BoardStatus::Ongoing {
+                break;
+            }
+            self.ai.set_castling(castling);
+            match self.ai.search(&board, reply_depth) {
+                Some((reply, _)) => {
+                    line.push(reply);
+                    let after = castling
+                        .castles(&board)
+                        .into_iter()
+                        .find(|(x, _)| *x == reply);
+                    castling = castling.after(&board, reply);
+                    board = match after {
+                        Some((_, after)) => after,
+                        None => board.make_move_new(reply),
+                    };
+                }
+                None => break,
+            }
+        }
+        Some(Analysis {
+            best_move,
+            score,
+            depth,
+            line,
+        })
+    }
+
+    fn root_search(&mut self, depth: i32) -> Option<(ChessMove, i32)> {
+        // Searches the current position
+        // In Chess960 the search gets the castling rights, which the board doesn't keep
+        let castling = self.castling_rooks();
+        self.ai.set_castling(castling);
+        self.ai.search(&self.board, depth)
+    }
+
+    fn castling_rooks(&self) -> CastlingRooks {
+        self.position960
+            .as_ref()
+            .map_or(CastlingRooks::default(), |position| {
+                position.castling_rooks()
+            })
+    }
+
+    fn board_after(&self, chess_move: ChessMove) -> Board {
+        // The board after a move, which can be a Chess960 castling move
+        match &self.position960 {
+            Some(position) => {
+                let mut position = position.clone();
+                position.make_move(chess_move);
+                position.uncastled().board
+            }
+            None => self.board.make_move_new(chess_move),
+        }
+    }
+
+    pub fn parse_move(&self, text: &str) -> Option<ChessMove> {
+        // Reads a legal move in coordinate notation or SAN
+        match &self.position960 {
+            Some(position) => position.parse_move(text),
+            None => parse_move(&self.board, text),
+        }
+    }
+
+    pub fn to_san(&self, chess_move: ChessMove) -> String {
+        // SAN of a legal move in the current position
+        match &self.position960 {
+            Some(position) => position.to_san(chess_move),
+            None => to_san(&self.board, chess_move),
         }
-        best_move.expect("Internal error, no move selected.")
     }
 
     pub fn play_display(&mut self) -> ChessMove {
@@ -41,48 +301,269 @@ impl Game {
         let depth = self.depth.unwrap_or(4);
         println!("Finding a move at depth: {}", depth);
         let start_best_move = Instant::now();
-        let best_move = self.ai.play(&self.board, depth);
+        let best_move = self.play();
         let duration_best_move = start_best_move.elapsed();
         println!("Time to find best_move_: {:?}", duration_best_move);
-        if best_move == None {
-            panic!("Internal error, Invalid position")
-        }
-        best_move.expect("Internal error, no move selected.")
+        best_move
     }
 
-    pub fn set_board(&mut self, fen: &str, moves: Vec<&str>) {
+    pub fn set_board(&mut self, fen: &str, moves: Vec<&str>) -> Result<(), String> {
         // Initialize the board from FEN and create a new game
-        let mut board = Board::from_str(fen).unwrap();
+        // Chess960 positions are read with their castling rights, which the board doesn't keep
+        // Returns an error and keeps the current game if the FEN can't be read
+        let start960 = match self.chess960 {
+            true => Some(Chess960::from_fen(fen)?),
+            false => None,
+        };
+        let start = match &start960 {
+            Some(position) => position.uncastled(),
+            None => Fen::parse(fen)?,
+        };
+        // The ply is computed from the side to move and the fullmove counter
+        let black_to_move = start.board.side_to_move() == Color::Black;
+        self.ply = 2 * start.fullmove.saturating_sub(1) + black_to_move as u32;
+
+        // GUIs send the whole game before every move
+        // The records of the moves which were already known are kept with their search info
+        let same_start = self.start == start
+            && self.start960.as_ref().map(|x| x.fen()) == start960.as_ref().map(|x| x.fen());
+        let mut previous = if same_start {
+            std::mem::take(&mut self.history)
+        } else {
+            Vec::new()
+        };
+        self.start = start;
+        self.position960 = start960.clone();
+        self.start960 = start960;
+        self.history.clear();
+        self.redo.clear();
+        self.board = start.board;
 
         // Puts all the given moves into the given board
-        for mv in moves {
-            let source = Square::make_square(
-                Rank::from_index(mv[1..2].parse::<usize>().unwrap() - 1),
-                File::from_index((mv.as_bytes()[0] - b'a') as usize),
-            );
-            let dest = Square::make_square(
-                Rank::from_index(mv[3..4].parse::<usize>().unwrap() - 1),
-                File::from_index((mv.as_bytes()[2] - b'a') as usize),
-            );
-            let promotion = if mv.len() > 4 {
-                Some(match mv.as_bytes()[4] {
-                    b'q' => Piece::Queen,
-                    b'r' => Piece::Rook,
-                    b'b' => Piece::Bishop,
-                    b'n' => Piece::Knight,
-                    _ => unreachable!(),
-                })
+        // The moves can be in coordinate notation or SAN, an invalid move ends the list
+        for (index, mv) in moves.into_iter().enumerate() {
+            let chess_move = match self.parse_move(mv) {
+                Some(chess_move) => chess_move,
+                None => break,
+            };
+            match previous.get(index) {
+                Some(record) if record.chess_move == chess_move => {
+                    let record = record.clone();
+                    self.advance(chess_move);
+                    self.history.push(record);
+                }
+                _ => {
+                    previous.clear();
+                    self.make_move(chess_move);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn make_move(&mut self, chess_move: ChessMove) {
+        // Plays a legal move and records it in the move list
+        // The info of the last search is kept if it selected this move
+        let (score, depth) = match self.last_search.take() {
+            Some((hash, mv, score, depth)) if hash == self.board.get_hash() && mv == chess_move => {
+                (Some(score), Some(depth))
+            }
+            _ => (None, None),
+        };
+        // A new move replaces the moves which were taken back
+        self.redo.clear();
+        self.history.push(MoveRecord {
+            board: self.board,
+            chess_move,
+            san: self.to_san(chess_move),
+            score,
+            depth,
+        });
+        self.advance(chess_move);
+    }
+
+    fn advance(&mut self, chess_move: ChessMove) {
+        // Plays a move on the board without recording it
+        self.board = self.board_after(chess_move);
+        if let Some(position) = &mut self.position960 {
+            position.make_move(chess_move);
+        }
+        self.ply += 1;
+    }
+
+    pub fn undo(&mut self) -> Option<ChessMove> {
+        // Takes back the last move, it can be played again with redo
+        let record = self.history.pop()?;
+        self.board = record.board;
+        self.ply -= 1;
+        self.last_search = None;
+        // A Chess960 position is replayed from the start to restore the castling rights
+        if let Some(start) = &self.start960 {
+            let mut position = start.clone();
+            for record in &self.history {
+                position.make_move(record.chess_move);
+            }
+            self.position960 = Some(position);
+        }
+        let chess_move = record.chess_move;
+        self.redo.push(record);
+        Some(chess_move)
+    }
+
+    pub fn redo(&mut self) -> Option<ChessMove> {
+        // Plays the last move which was taken back, with its search info
+        let record = self.redo.pop()?;
+        self.advance(record.chess_move);
+        let chess_move = record.chess_move;
+        self.history.push(record);
+        Some(chess_move)
+    }
+
+    pub fn go_to(&mut self, index: usize) -> bool {
+        // Moves to the position after the given number of moves of the game
+        // The moves after it stay available for redo
+        // Returns false if the game doesn't have that many moves
+        if index > self.history.len() + self.redo.len() {
+            return false;
+        }
+        while self.history.len() > index {
+            self.undo();
+        }
+        while self.history.len() < index {
+            self.redo();
+        }
+        true
+    }
+
+    pub fn fen(&self) -> String {
+        // FEN of the current position with the move counters continued from the starting position
+        // Chess960 positions use X-FEN castling rights
+        if let Some(position) = &self.position960 {
+            return position.fen();
+        }
+        let mut halfmove = self.start.halfmove;
+        // The halfmove clock is reset by pawn moves and captures
+        for record in &self.history {
+            let pawn_move =
+                record.board.piece_on(record.chess_move.get_source()) == Some(Piece::Pawn);
+            let capture = record
+                .board
+                .piece_on(record.chess_move.get_dest())
+                .is_some();
+            halfmove = if pawn_move || capture {
+                0
             } else {
-                None
+                halfmove + 1
             };
+        }
+        let mut fen = Fen::new(self.board, halfmove, self.ply / 2 + 1);
+        // The en passant square of the starting position is kept even if no pawn can take
+        if self.history.is_empty() {
+            fen.en_passant = self.start.en_passant;
+        }
+        fen.to_string()
+    }
 
-            let chess_move = ChessMove::new(source, dest, promotion);
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
 
-            // Applies the move to the board
-            board = board.make_move_new(chess_move);
+    pub fn next_redo(&self) -> Option<&MoveRecord> {
+        // The move which redo plays next
+        self.redo.last()
+    }
+
+    pub fn result(&self) -> &'static str {
+        // Result of the game in PGN notation, "*" if it hasn't ended
+        // Repetitions and the 50 move rule are not detected
+        if let Some(position) = &self.position960 {
+            if position.is_checkmate() {
+                return if self.board.side_to_move() == Color::White {
+                    "0-1"
+                } else {
+                    "1-0"
+                };
+            }
+            if position.is_stalemate() {
+                return "1/2-1/2";
+            }
+        }
+        match self.board.status() {
+            BoardStatus::Checkmate if self.board.side_to_move() == Color::White => "0-1",
+            BoardStatus::Checkmate => "1-0",
+            BoardStatus::Stalemate => "1/2-1/2",
+            BoardStatus::Ongoing if is_insufficient_material(&self.board) => "1/2-1/2",
+            BoardStatus::Ongoing => "*",
+        }
+    }
+
+    pub fn to_pgn(&self, white: &str, black: &str, comments: bool) -> String {
+        // Exports the game as PGN
+        // With comments, the engine's moves get their score from white's point of view and depth, e.g. {+0.35/4}
+        let result = self.result();
+        let mut tags: Vec<(String, String)> = [
+            ("Event", "Rusty Mind game"),
+            ("Site", "?"),
+            ("Date", &today()),
+            ("Round", "-"),
+            ("White", white),
+            ("Black", black),
+            ("Result", result),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let start_fen = match &self.start960 {
+            Some(start) => {
+                tags.push(("Variant".to_string(), "Chess960".to_string()));
+                start.fen()
+            }
+            None => self.start.to_string(),
+        };
+        if start_fen != STARTING_FEN || self.chess960 {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), start_fen));
         }
 
-        // Updates the board to reflect the final state
-        self.board = board;
+        let mut white_to_move = self.start.board.side_to_move() == Color::White;
+        let mut game_comments = Vec::new();
+        for (index, record) in self.history.iter().enumerate() {
+            if let (Some(score), Some(depth), true) = (record.score, record.depth, comments) {
+                let score = if white_to_move { score } else { -score };
+                game_comments.push((index + 1, format_score(score, depth)));
+            }
+            white_to_move = !white_to_move;
+        }
+        PgnGame {
+            tags,
+            moves: self.history.iter().map(|x| x.san.clone()).collect(),
+            comments: game_comments,
+            result: result.to_string(),
+            ..PgnGame::default()
+        }
+        .to_pgn()
     }
 }
+
+pub fn format_score(score: i32, depth: i32) -> String {
+    // Score in pawns with the search depth as used in PGN comments, e.g. +0.35/4
+    format!("{:+.2}/{}", score as f64 / 100.0, depth)
+}
+
+fn today() -> String {
+    // Current date in the PGN format YYYY.MM.DD (UTC)
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs() / 86_400) as i64;
+    // Converts days since 1970-01-01 to a civil date
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
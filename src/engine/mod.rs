@@ -1,8 +1,46 @@
+mod attacks;
+mod bench;
+mod book;
+mod chess960;
+mod endgame;
+mod epd;
 mod evaluation;
+mod fen;
 mod game;
 mod helpers;
+mod movegen;
+mod movepick;
+mod nnue;
+mod notation;
+mod perft;
+mod pgn;
+mod position;
 mod search;
+mod tablebase;
 mod tables;
+mod tunables;
+mod weights;
 
+pub use bench::*;
+pub use book::*;
+pub use chess960::{CastlingRooks, Chess960};
+pub use endgame::{endgame_evaluation, is_insufficient_material, MaterialCount};
+pub use epd::*;
+pub use evaluation::{
+    bad_bishop, bishop_pair, evaluation, material, outposts, piece_terms, rook_files,
+    rook_seventh_rank, trapped_pieces, Classical, Evaluator,
+};
+pub use fen::*;
 pub use game::*;
+pub use helpers::filter_moves;
+pub use movegen::*;
+pub use movepick::{see, History, MovePicker, MAX_PLY};
+pub use nnue::{Network, Nnue};
+pub use notation::*;
+pub use perft::*;
+pub use pgn::*;
+pub use position::{Move, MoveList, Position, MAX_MOVES};
 pub use search::*;
+pub use tablebase::{Tablebase, TB_WIN};
+pub use tunables::{find_tunable, spsa_input, Tunable, TUNABLES};
+pub use weights::*;
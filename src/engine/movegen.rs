@@ -0,0 +1,117 @@
+use super::position::{MoveList, Position};
+use chess::{Board, ChessMove, MoveGen};
+use std::fmt;
+
+pub trait MoveGenerator {
+    // A board which generates the legal moves and plays them with make and unmake
+    // Implemented by the chess crate's board and by the bitboard Position of this crate
+    // perft_with and the search run on either of them
+    type Move: Copy + Default + PartialEq + fmt::Display;
+
+    fn legal_moves(&mut self) -> MoveList<Self::Move>;
+    fn make_move(&mut self, mv: Self::Move);
+    fn unmake_move(&mut self);
+    // The hash of the position, each backend has its own keys
+    fn hash(&self) -> u64;
+    fn in_check(&self) -> bool;
+    // The position as a chess crate board, which the evaluators, the move ordering and the tablebases read
+    fn to_board(&self) -> Board;
+    // The backend's move for a legal chess crate move, with the same squares and promotion
+    fn find_move(&mut self, chess_move: ChessMove) -> Option<Self::Move>;
+}
+
+pub struct CopyMake {
+    // The chess crate board, which only has copy-make
+    // The boards of the positions before the current one are kept for unmake_move
+    boards: Vec<Board>,
+}
+
+impl CopyMake {
+    pub fn new(board: Board) -> CopyMake {
+        CopyMake {
+            boards: vec![board],
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        self.boards.last().unwrap()
+    }
+
+    pub fn set_board(&mut self, board: Board) {
+        // Starts again from the given board, the stack keeps its memory
+        self.boards.clear();
+        self.boards.push(board);
+    }
+}
+
+impl MoveGenerator for CopyMake {
+    type Move = ChessMove;
+
+    fn legal_moves(&mut self) -> MoveList<ChessMove> {
+        let mut moves = MoveList::new();
+        for mv in MoveGen::new_legal(self.board()) {
+            moves.push(mv);
+        }
+        moves
+    }
+
+    fn make_move(&mut self, mv: ChessMove) {
+        let board = self.board().make_move_new(mv);
+        self.boards.push(board);
+    }
+
+    fn unmake_move(&mut self) {
+        if self.boards.len() > 1 {
+            self.boards.pop();
+        }
+    }
+
+    fn hash(&self) -> u64 {
+        self.board().get_hash()
+    }
+
+    fn in_check(&self) -> bool {
+        self.board().checkers().popcnt() > 0
+    }
+
+    fn to_board(&self) -> Board {
+        *self.board()
+    }
+
+    fn find_move(&mut self, chess_move: ChessMove) -> Option<ChessMove> {
+        // The moves are the same, checking the legality again would generate every move
+        Some(chess_move)
+    }
+}
+
+impl MoveGenerator for Position {
+    type Move = super::position::Move;
+
+    fn legal_moves(&mut self) -> MoveList {
+        Position::legal_moves(self)
+    }
+
+    fn make_move(&mut self, mv: Self::Move) {
+        Position::make_move(self, mv)
+    }
+
+    fn unmake_move(&mut self) {
+        Position::unmake_move(self)
+    }
+
+    fn hash(&self) -> u64 {
+        Position::hash(self)
+    }
+
+    fn in_check(&self) -> bool {
+        Position::in_check(self)
+    }
+
+    fn to_board(&self) -> Board {
+        Position::to_board(self)
+    }
+
+    fn find_move(&mut self, chess_move: ChessMove) -> Option<Self::Move> {
+        Position::find_move(self, chess_move)
+    }
+}
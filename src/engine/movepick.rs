@@ -0,0 +1,278 @@
+use super::position::MoveList;
+use chess::{
+    get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_rook_moves, BitBoard,
+    Board, ChessMove, Color, MoveGen, Piece, Square, ALL_PIECES, EMPTY,
+};
+
+// Deepest ply which has its own killer moves
+pub const MAX_PLY: usize = 128;
+
+// Piece values used to order the captures and in the static exchange evaluation
+const SEE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 20_000];
+
+// Quiet queen promotions are tried before the other quiet moves
+const PROMOTION_SCORE: i32 = 1 << 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    TtMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Killers,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+pub struct History {
+    // Score of every quiet move by side, source and destination square
+    // Raised whenever the move causes a beta cutoff
+    table: [[[i32; 64]; 64]; 2],
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            table: [[[0; 64]; 64]; 2],
+        }
+    }
+
+    pub fn score(&self, color: Color, mv: ChessMove) -> i32 {
+        self.table[color.to_index()][mv.get_source().to_index()][mv.get_dest().to_index()]
+    }
+
+    pub fn update(&mut self, color: Color, mv: ChessMove, depth: i32) {
+        let entry =
+            &mut self.table[color.to_index()][mv.get_source().to_index()][mv.get_dest().to_index()];
+        *entry = entry.saturating_add(depth * depth);
+    }
+
+    pub fn age(&mut self) {
+        // Halves every score so the moves of the previous searches count less
+        for entry in self.table.iter_mut().flatten().flatten() {
+            *entry /= 2;
+        }
+    }
+}
+
+pub struct MovePicker {
+    // Returns the legal moves of a position in stages:
+    // the move from the hash, the captures which don't lose material, the killer moves,
+    // the quiet moves by their history score and at last the captures which lose material
+    // A stage is only generated once the previous ones are used up, so a beta cutoff on an
+    // early move saves the generation and sorting of the quiet moves
+    stage: Stage,
+    moves: MoveGen,
+    tt_move: Option<ChessMove>,
+    killers: [Option<ChessMove>; 2],
+    killer_index: usize,
+    scored: MoveList<(ChessMove, i32)>,
+    bad_captures: MoveList<(ChessMove, i32)>,
+}
+
+impl MovePicker {
+    pub fn new(
+        board: &Board,
+        tt_move: Option<ChessMove>,
+        killers: [Option<ChessMove>; 2],
+    ) -> MovePicker {
+        MovePicker {
+            stage: Stage::TtMove,
+            moves: MoveGen::new_legal(board),
+            // The hash move may come from a different position with the same key
+            tt_move: tt_move.filter(|mv| board.legal(*mv)),
+            killers,
+            killer_index: 0,
+            scored: MoveList::new(),
+            bad_captures: MoveList::new(),
+        }
+    }
+
+    pub fn next(&mut self, board: &Board, history: &History) -> Option<ChessMove> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if self.tt_move.is_some() {
+                        return self.tt_move;
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    // The generator only yields the moves to the masked squares, the rest is kept for later
+                    self.moves.set_iterator_mask(capture_targets(board));
+                    for mv in &mut self.moves {
+                        if Some(mv) == self.tt_move {
+                            continue;
+                        }
+                        let score = mvv_lva(board, mv);
+                        if see(board, mv) >= 0 {
+                            self.scored.push((mv, score));
+                        } else {
+                            self.bad_captures.push((mv, score));
+                        }
+                    }
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match pick_best(&mut self.scored) {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::Killers,
+                },
+                Stage::Killers => {
+                    while self.killer_index < self.killers.len() {
+                        let killer = self.killers[self.killer_index];
+                        self.killer_index += 1;
+                        if let Some(mv) = killer {
+                            // Moves to the en passant square were already returned with the captures
+                            if Some(mv) != self.tt_move
+                                && capture_targets(board) & BitBoard::from_square(mv.get_dest())
+                                    == EMPTY
+                                && board.legal(mv)
+                            {
+                                return Some(mv);
+                            }
+                        }
+                    }
+                    self.stage = Stage::GenerateQuiets;
+                }
+                Stage::GenerateQuiets => {
+                    self.moves.set_iterator_mask(!EMPTY);
+                    let color = board.side_to_move();
+                    for mv in &mut self.moves {
+                        // Every remaining move is quiet and legal, so the killers among them were already returned
+                        if Some(mv) == self.tt_move || self.killers.contains(&Some(mv)) {
+                            continue;
+                        }
+                        let score = if mv.get_promotion() == Some(Piece::Queen) {
+                            PROMOTION_SCORE
+                        } else {
+                            history.score(color, mv)
+                        };
+                        self.scored.push((mv, score));
+                    }
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match pick_best(&mut self.scored) {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::BadCaptures,
+                },
+                Stage::BadCaptures => match pick_best(&mut self.bad_captures) {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+fn capture_targets(board: &Board) -> BitBoard {
+    // The enemy pieces and the en passant square
+    let mut targets = *board.color_combined(!board.side_to_move());
+    if let Some(square) = board.en_passant() {
+        targets |= BitBoard::from_square(square.uforward(board.side_to_move()));
+    }
+    targets
+}
+
+pub fn is_quiet(board: &Board, mv: ChessMove) -> bool {
+    // Checks whether a move captures nothing, en passant counts as a capture
+    board.piece_on(mv.get_dest()).is_none()
+        && !(board.piece_on(mv.get_source()) == Some(Piece::Pawn)
+            && mv.get_source().get_file() != mv.get_dest().get_file())
+}
+
+pub fn see(board: &Board, mv: ChessMove) -> i32 {
+    // Static exchange evaluation: the material won on the destination square when both sides
+    // keep capturing there with their least valuable piece and may stop whenever it suits them
+    let dest = mv.get_dest();
+    let mut from = BitBoard::from_square(mv.get_source());
+    let mut attacker = match board.piece_on(mv.get_source()) {
+        Some(piece) => piece,
+        None => return 0,
+    };
+    let mut occupied = *board.combined();
+    let victim = match board.piece_on(dest) {
+        Some(piece) => piece,
+        None if attacker == Piece::Pawn && mv.get_source().get_file() != dest.get_file() => {
+            // En passant, the captured pawn isn't on the destination square
+            occupied ^= BitBoard::from_square(dest.ubackward(board.side_to_move()));
+            Piece::Pawn
+        }
+        None => return 0,
+    };
+
+    let mut gain = [0; 32];
+    let mut depth = 0;
+    let mut side = board.side_to_move();
+    gain[0] = SEE_VALUES[victim.to_index()];
+    loop {
+        depth += 1;
+        // Speculative score if the piece which just captured is taken back
+        gain[depth] = SEE_VALUES[attacker.to_index()] - gain[depth - 1];
+        if depth == gain.len() - 1 {
+            break;
+        }
+        occupied ^= from;
+        side = !side;
+        // The attackers are found again with the new occupancy, so pieces behind the last one join in
+        match least_valuable_attacker(board, dest, occupied, side) {
+            Some((square, piece)) => {
+                from = square;
+                attacker = piece;
+            }
+            None => break,
+        }
+    }
+    while depth > 1 {
+        depth -= 1;
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+    }
+    gain[0]
+}
+
+pub(super) fn least_valuable_attacker(
+    board: &Board,
+    square: Square,
+    occupied: BitBoard,
+    color: Color,
+) -> Option<(BitBoard, Piece)> {
+    let own = board.color_combined(color) & occupied;
+    for piece in ALL_PIECES {
+        let pieces = board.pieces(piece) & own;
+        if pieces == EMPTY {
+            continue;
+        }
+        let attacks = match piece {
+            Piece::Pawn => get_pawn_attacks(square, !color, pieces),
+            Piece::Knight => get_knight_moves(square),
+            Piece::Bishop => get_bishop_moves(square, occupied),
+            Piece::Rook => get_rook_moves(square, occupied),
+            Piece::Queen => get_bishop_moves(square, occupied) | get_rook_moves(square, occupied),
+            Piece::King => get_king_moves(square),
+        } & pieces;
+        if attacks != EMPTY {
+            return Some((BitBoard::from_square(attacks.to_square()), piece));
+        }
+    }
+    None
+}
+
+fn mvv_lva(board: &Board, mv: ChessMove) -> i32 {
+    // Most valuable victim first, then least valuable attacker
+    let victim = board.piece_on(mv.get_dest()).unwrap_or(Piece::Pawn);
+    let attacker = board.piece_on(mv.get_source()).unwrap_or(Piece::Pawn);
+    SEE_VALUES[victim.to_index()] * 8 - attacker.to_index() as i32
+}
+
+fn pick_best(moves: &mut MoveList<(ChessMove, i32)>) -> Option<ChessMove> {
+    // Selection sort one move at a time, most stages are left before all their moves are tried
+    let index = (0..moves.len()).max_by_key(|&index| moves[index].1)?;
+    Some(moves.swap_remove(index).0)
+}
@@ -0,0 +1,207 @@
+use super::evaluation::Evaluator;
+use chess::{Board, BoardStatus, Color, Square, ALL_COLORS, ALL_PIECES};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Simple 768 -> N -> 1 network evaluated from the perspective of both sides
+// Every (color, piece, square) combination is one input feature
+// The hidden layer is kept as two accumulators, one seen from each side
+//
+// File format (all numbers little endian):
+//     b"RMNN", hidden layer size N as u32,
+//     feature weights as i16 [768 * N] (input major),
+//     feature biases as i16 [N],
+//     output weights as i16 [2 * N] (side to move first),
+//     output bias as i32
+
+const INPUTS: usize = 768;
+const MAGIC: &[u8; 4] = b"RMNN";
+// Quantization of the hidden layer and of the output weights
+const QA: i32 = 255;
+const QB: i32 = 64;
+// Converts the network output into centipawns
+const SCALE: i32 = 400;
+
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+impl Network {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Network> {
+        let bytes = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid network file");
+        if bytes.len() < 8 || &bytes[0..4] != MAGIC {
+            return Err(invalid());
+        }
+        let hidden = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        if hidden == 0 || bytes.len() != 8 + 2 * (INPUTS * hidden + hidden + 2 * hidden) + 4 {
+            return Err(invalid());
+        }
+        let mut offset = 8;
+        let mut read_i16s = |count: usize| -> Vec<i16> {
+            let values = bytes[offset..offset + 2 * count]
+                .chunks_exact(2)
+                .map(|x| i16::from_le_bytes([x[0], x[1]]))
+                .collect();
+            offset += 2 * count;
+            values
+        };
+        let feature_weights = read_i16s(INPUTS * hidden);
+        let feature_biases = read_i16s(hidden);
+        let output_weights = read_i16s(2 * hidden);
+        let output_bias = i32::from_le_bytes(bytes[bytes.len() - 4..].try_into().unwrap());
+        Ok(Network {
+            hidden,
+            feature_weights,
+            feature_biases,
+            output_weights,
+            output_bias,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct Accumulator {
+    // Hidden layer values seen from white's and from black's side
+    white: Vec<i16>,
+    black: Vec<i16>,
+}
+
+pub struct Nnue {
+    network: Network,
+    // Accumulators of the positions on the current search path
+    // They are kept allocated between searches, only the first ply + 1 of them are valid
+    stack: Vec<Accumulator>,
+    ply: usize,
+}
+
+impl Nnue {
+    pub fn new(network: Network) -> Nnue {
+        let empty = Accumulator {
+            white: network.feature_biases.clone(),
+            black: network.feature_biases.clone(),
+        };
+        Nnue {
+            network,
+            stack: vec![empty],
+            ply: 0,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Nnue> {
+        Ok(Nnue::new(Network::load(path)?))
+    }
+
+    fn update(&mut self, color: Color, piece: usize, square: Square, add: bool) {
+        // Adds or removes one input feature in both accumulators of the current position
+        let hidden = self.network.hidden;
+        let accumulator = &mut self.stack[self.ply];
+        for (perspective, values) in [
+            (Color::White, &mut accumulator.white),
+            (Color::Black, &mut accumulator.black),
+        ] {
+            let index = feature_index(perspective, color, piece, square) * hidden;
+            let weights = &self.network.feature_weights[index..index + hidden];
+            for (value, weight) in values.iter_mut().zip(weights) {
+                // Wrapping keeps the updates consistent with a refresh even if a network overflows
+                if add {
+                    *value = value.wrapping_add(*weight);
+                } else {
+                    *value = value.wrapping_sub(*weight);
+                }
+            }
+        }
+    }
+}
+
+impl Evaluator for Nnue {
+    fn reset(&mut self, board: &Board) {
+        // Builds the accumulators of the root position from scratch
+        self.ply = 0;
+        let accumulator = &mut self.stack[0];
+        accumulator
+            .white
+            .copy_from_slice(&self.network.feature_biases);
+        accumulator
+            .black
+            .copy_from_slice(&self.network.feature_biases);
+        for color in ALL_COLORS {
+            for (piece, kind) in ALL_PIECES.iter().enumerate() {
+                for square in *board.pieces(*kind) & board.color_combined(color) {
+                    self.update(color, piece, square, true);
+                }
+            }
+        }
+    }
+
+    fn make_move(&mut self, before: &Board, after: &Board) {
+        // Only the features of the pieces which changed squares are updated
+        self.ply += 1;
+        if self.stack.len() == self.ply {
+            let accumulator = self.stack[self.ply - 1].clone();
+            self.stack.push(accumulator);
+        } else {
+            let (previous, next) = self.stack.split_at_mut(self.ply);
+            let (previous, next) = (&previous[self.ply - 1], &mut next[0]);
+            next.white.copy_from_slice(&previous.white);
+            next.black.copy_from_slice(&previous.black);
+        }
+        for color in ALL_COLORS {
+            for (piece, kind) in ALL_PIECES.iter().enumerate() {
+                let old = *before.pieces(*kind) & before.color_combined(color);
+                let new = *after.pieces(*kind) & after.color_combined(color);
+                for square in old & !new {
+                    self.update(color, piece, square, false);
+                }
+                for square in new & !old {
+                    self.update(color, piece, square, true);
+                }
+            }
+        }
+    }
+
+    fn unmake_move(&mut self) {
+        self.ply = self.ply.saturating_sub(1);
+    }
+
+    fn evaluate(&mut self, board: &Board, _alpha: i32, _beta: i32) -> i32 {
+        // Evaluation relative to the side to move
+        match board.status() {
+            BoardStatus::Checkmate => return -99_999,
+            BoardStatus::Stalemate => return 0,
+            BoardStatus::Ongoing => (),
+        }
+        let accumulator = &self.stack[self.ply];
+        let (us, them) = if board.side_to_move() == Color::White {
+            (&accumulator.white, &accumulator.black)
+        } else {
+            (&accumulator.black, &accumulator.white)
+        };
+        let hidden = self.network.hidden;
+        let mut output: i64 = 0;
+        for (values, weights) in [
+            (us, &self.network.output_weights[..hidden]),
+            (them, &self.network.output_weights[hidden..]),
+        ] {
+            for (value, weight) in values.iter().zip(weights) {
+                output += (*value as i32).clamp(0, QA) as i64 * *weight as i64;
+            }
+        }
+        ((output + self.network.output_bias as i64) * SCALE as i64 / (QA * QB) as i64) as i32
+    }
+}
+
+fn feature_index(perspective: Color, color: Color, piece: usize, square: Square) -> usize {
+    // Black sees the board mirrored with its own pieces as the first 384 inputs
+    let (side, square) = if perspective == Color::White {
+        (color == Color::Black, square.to_index())
+    } else {
+        (color == Color::White, square.to_index() ^ 56)
+    };
+    side as usize * 384 + piece * 64 + square
+}
@@ -0,0 +1,192 @@
+use chess::{Board, BoardStatus, ChessMove, Color, File, MoveGen, Piece, Rank, Square, EMPTY};
+
+pub fn parse_san(board: &Board, san: &str) -> Option<ChessMove> {
+    // Finds the legal move described by a move in Standard Algebraic Notation
+    // Returns None if the notation is invalid, illegal or ambiguous
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    let back_rank = if board.side_to_move() == Color::White {
+        Rank::First
+    } else {
+        Rank::Eighth
+    };
+    let castle = match san {
+        "O-O" | "0-0" => Some(File::G),
+        "O-O-O" | "0-0-0" => Some(File::C),
+        _ => None,
+    };
+    if let Some(file) = castle {
+        let chess_move = ChessMove::new(
+            Square::make_square(back_rank, File::E),
+            Square::make_square(back_rank, file),
+            None,
+        );
+        return MoveGen::new_legal(board)
+            .find(|mv| *mv == chess_move && board.piece_on(mv.get_source()) == Some(Piece::King));
+    }
+
+    let (san, promotion) = match san.split_once('=') {
+        Some((rest, piece)) => (rest, Some(piece_from_char(piece.chars().next()?)?)),
+        None => match san.chars().last() {
+            // Promotions are sometimes written without the '='
+            Some(last @ ('Q' | 'R' | 'B' | 'N')) => {
+                (&san[..san.len() - 1], Some(piece_from_char(last)?))
+            }
+            _ => (san, None),
+        },
+    };
+    let mut chars: Vec<char> = san.chars().filter(|c| *c != 'x' && *c != '-').collect();
+    let piece = match chars.first() {
+        Some(c) if c.is_ascii_uppercase() => {
+            let piece = piece_from_char(*c)?;
+            chars.remove(0);
+            piece
+        }
+        _ => Piece::Pawn,
+    };
+    if chars.len() < 2 {
+        return None;
+    }
+    let dest = square_from_chars(chars[chars.len() - 2], chars[chars.len() - 1])?;
+    // The characters in between are the file and/or the rank of the moving piece
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in &chars[..chars.len() - 2] {
+        match c {
+            'a'..='h' => from_file = Some(File::from_index((*c as u8 - b'a') as usize)),
+            '1'..='8' => from_rank = Some(Rank::from_index((*c as u8 - b'1') as usize)),
+            _ => return None,
+        }
+    }
+
+    let mut candidates = MoveGen::new_legal(board).filter(|mv| {
+        mv.get_dest() == dest
+            && mv.get_promotion() == promotion
+            && board.piece_on(mv.get_source()) == Some(piece)
+            && from_file.is_none_or(|file| mv.get_source().get_file() == file)
+            && from_rank.is_none_or(|rank| mv.get_source().get_rank() == rank)
+    });
+    let chess_move = candidates.next()?;
+    if candidates.next().is_some() {
+        return None;
+    }
+    Some(chess_move)
+}
+
+pub fn to_san(board: &Board, chess_move: ChessMove) -> String {
+    // Writes a legal move in Standard Algebraic Notation, e.g. Nbd7, exd6, e8=Q+, O-O-O#
+    let source = chess_move.get_source();
+    let dest = chess_move.get_dest();
+    let piece = board.piece_on(source).unwrap_or(Piece::Pawn);
+    let capture = board.piece_on(dest).is_some()
+        || (piece == Piece::Pawn && source.get_file() != dest.get_file());
+    let mut san = String::new();
+
+    if piece == Piece::King && source.get_file() == File::E && dest.get_file() == File::G {
+        san.push_str("O-O");
+    } else if piece == Piece::King && source.get_file() == File::E && dest.get_file() == File::C {
+        san.push_str("O-O-O");
+    } else if piece == Piece::Pawn {
+        if capture {
+            san.push(file_char(source.get_file()));
+            san.push('x');
+        }
+        san.push_str(&dest.to_string());
+        if let Some(promotion) = chess_move.get_promotion() {
+            san.push('=');
+            san.push(piece_char(promotion));
+        }
+    } else {
+        san.push(piece_char(piece));
+        // Other pieces of the same kind which can move to the same square
+        let others: Vec<Square> = MoveGen::new_legal(board)
+            .filter(|mv| {
+                mv.get_dest() == dest
+                    && mv.get_source() != source
+                    && board.piece_on(mv.get_source()) == Some(piece)
+            })
+            .map(|mv| mv.get_source())
+            .collect();
+        if !others.is_empty() {
+            if others.iter().all(|x| x.get_file() != source.get_file()) {
+                san.push(file_char(source.get_file()));
+            } else if others.iter().all(|x| x.get_rank() != source.get_rank()) {
+                san.push(rank_char(source.get_rank()));
+            } else {
+                san.push_str(&source.to_string());
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&dest.to_string());
+    }
+
+    let after = board.make_move_new(chess_move);
+    if after.status() == BoardStatus::Checkmate {
+        san.push('#');
+    } else if after.checkers() != &EMPTY {
+        san.push('+');
+    }
+    san
+}
+
+pub fn parse_uci(board: &Board, text: &str) -> Option<ChessMove> {
+    // Finds the legal move written in coordinate notation, e.g. e2e4 or e7e8q
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() != 4 && chars.len() != 5 {
+        return None;
+    }
+    let source = square_from_chars(chars[0], chars[1])?;
+    let dest = square_from_chars(chars[2], chars[3])?;
+    let promotion = match chars.get(4) {
+        Some(c) => Some(piece_from_char(c.to_ascii_uppercase()).filter(|x| *x != Piece::King)?),
+        None => None,
+    };
+    let chess_move = ChessMove::new(source, dest, promotion);
+    MoveGen::new_legal(board).find(|mv| *mv == chess_move)
+}
+
+pub fn parse_move(board: &Board, text: &str) -> Option<ChessMove> {
+    // Accepts both coordinate notation and SAN
+    parse_uci(board, text).or_else(|| parse_san(board, text))
+}
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    match c {
+        'K' => Some(Piece::King),
+        'Q' => Some(Piece::Queen),
+        'R' => Some(Piece::Rook),
+        'B' => Some(Piece::Bishop),
+        'N' => Some(Piece::Knight),
+        _ => None,
+    }
+}
+
+fn square_from_chars(file: char, rank: char) -> Option<Square> {
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return None;
+    }
+    Some(Square::make_square(
+        Rank::from_index((rank as u8 - b'1') as usize),
+        File::from_index((file as u8 - b'a') as usize),
+    ))
+}
+
+pub(super) fn piece_char(piece: Piece) -> char {
+    match piece {
+        Piece::King => 'K',
+        Piece::Queen => 'Q',
+        Piece::Rook => 'R',
+        Piece::Bishop => 'B',
+        Piece::Knight => 'N',
+        Piece::Pawn => 'P',
+    }
+}
+
+pub(super) fn file_char(file: File) -> char {
+    (b'a' + file.to_index() as u8) as char
+}
+
+pub(super) fn rank_char(rank: Rank) -> char {
+    (b'1' + rank.to_index() as u8) as char
+}
@@ -0,0 +1,45 @@
+use super::movegen::MoveGenerator;
+use chess::{Board, ChessMove, MoveGen};
+
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    // Counts the leaf nodes of the move tree of the given depth
+    // Used to validate the move generation and the position setup
+    if depth == 0 {
+        return 1;
+    }
+    let moves = MoveGen::new_legal(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .map(|mv| perft(&board.make_move_new(mv), depth - 1))
+        .sum()
+}
+
+pub fn divide(board: &Board, depth: u32) -> Vec<(ChessMove, u64)> {
+    // Returns the perft count below every root move
+    if depth == 0 {
+        return Vec::new();
+    }
+    MoveGen::new_legal(board)
+        .map(|mv| (mv, perft(&board.make_move_new(mv), depth - 1)))
+        .collect()
+}
+
+pub fn perft_with<G: MoveGenerator>(generator: &mut G, depth: u32) -> u64 {
+    // perft with any of the move generation backends, the moves are played with make and unmake
+    if depth == 0 {
+        return 1;
+    }
+    let moves = generator.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mv in moves.iter() {
+        generator.make_move(*mv);
+        nodes += perft_with(generator, depth - 1);
+        generator.unmake_move();
+    }
+    nodes
+}
@@ -0,0 +1,268 @@
+use super::fen::Fen;
+use super::notation::parse_san;
+use chess::{Board, ChessMove};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Clone, Debug, Default)]
+pub struct PgnGame {
+    // One game read from a PGN file, or one variation inside a game
+    // Annotations are stored with the number of moves of the line played before them:
+    // 0 is before the first move, n is after the n-th move
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub comments: Vec<(usize, String)>,
+    // Numeric annotation glyphs, e.g. 2 for "?" and 4 for "??"
+    pub nags: Vec<(usize, u8)>,
+    // Alternatives to the n-th move, they start from the position before it
+    pub variations: Vec<(usize, PgnGame)>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn is_empty(&self) -> bool {
+        // Neither tags nor moves, e.g. only a comment
+        self.tags.is_empty() && self.moves.is_empty()
+    }
+
+    pub fn starting_board(&self) -> Option<Board> {
+        // The FEN tag is used for games which didn't start from the standard position
+        Fen::parse(self.tag("FEN").unwrap_or(STARTING_FEN))
+            .ok()
+            .map(|fen| fen.board)
+    }
+
+    pub fn replay(&self) -> Option<Vec<(Board, ChessMove)>> {
+        // Returns every position of the game together with the move played in it
+        // Returns None if any of the moves is illegal
+        let mut board = self.starting_board()?;
+        let mut positions = Vec::with_capacity(self.moves.len());
+        for san in &self.moves {
+            let chess_move = parse_san(&board, san)?;
+            positions.push((board, chess_move));
+            board = board.make_move_new(chess_move);
+        }
+        Some(positions)
+    }
+
+    pub fn to_pgn(&self) -> String {
+        // Writes the game in PGN export format, the movetext is wrapped at 80 characters
+        let mut pgn = String::new();
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        // Move numbers continue from the FEN tag
+        let fen_fields: Vec<&str> = self
+            .tag("FEN")
+            .unwrap_or(STARTING_FEN)
+            .split_whitespace()
+            .collect();
+        let fullmove = fen_fields
+            .get(5)
+            .and_then(|x| x.parse::<u32>().ok())
+            .unwrap_or(1);
+        let ply = 2 * fullmove.saturating_sub(1) + (fen_fields.get(1) == Some(&"b")) as u32;
+        let mut tokens = Vec::new();
+        self.write_line(ply, &mut tokens);
+        tokens.push(if self.result.is_empty() {
+            "*".to_string()
+        } else {
+            self.result.clone()
+        });
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push_str("\n\n");
+        pgn
+    }
+
+    fn write_line(&self, ply: u32, tokens: &mut Vec<String>) {
+        // Writes the moves of the line and its annotations, ply is the number of the first move
+        // counted in half moves from the start of the game
+        for (_, comment) in self.comments.iter().filter(|(n, _)| *n == 0) {
+            tokens.push(format!("{{{}}}", comment));
+        }
+        // Black's moves get a number at the start and after annotations
+        let mut numbered = false;
+        for (index, san) in self.moves.iter().enumerate() {
+            let current = ply + index as u32;
+            if current.is_multiple_of(2) {
+                tokens.push(format!("{}.", current / 2 + 1));
+            } else if !numbered {
+                tokens.push(format!("{}...", current / 2 + 1));
+            }
+            tokens.push(san.clone());
+            numbered = true;
+            for (_, nag) in self.nags.iter().filter(|(n, _)| *n == index + 1) {
+                tokens.push(format!("${}", nag));
+            }
+            for (_, comment) in self.comments.iter().filter(|(n, _)| *n == index + 1) {
+                tokens.push(format!("{{{}}}", comment));
+                numbered = false;
+            }
+            for (_, variation) in self.variations.iter().filter(|(n, _)| *n == index + 1) {
+                let mut inner = Vec::new();
+                variation.write_line(current, &mut inner);
+                if let Some(first) = inner.first_mut() {
+                    first.insert(0, '(');
+                }
+                if let Some(last) = inner.last_mut() {
+                    last.push(')');
+                }
+                tokens.extend(inner);
+                numbered = false;
+            }
+        }
+    }
+}
+
+pub fn read_games(text: &str) -> Vec<PgnGame> {
+    // Splits a PGN file into games
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    // Variations which are being read with the number of moves of their parent line before them
+    let mut stack: Vec<(PgnGame, usize)> = Vec::new();
+    let mut in_movetext = false;
+    let mut chars = text.chars().peekable();
+    let mut token = String::new();
+
+    while let Some(c) = chars.next() {
+        // Every delimiter ends the current token
+        if (c.is_whitespace() || "[{;()$".contains(c)) && !token.is_empty() {
+            let word = std::mem::take(&mut token);
+            in_movetext = true;
+            let line = current_line(&mut game, &mut stack);
+            if push_token(line, &word) && stack.is_empty() {
+                games.push(std::mem::take(&mut game));
+                in_movetext = false;
+            }
+        }
+        match c {
+            '[' if stack.is_empty() => {
+                if in_movetext {
+                    // A comment between two games doesn't make a game of its own
+                    let previous = std::mem::take(&mut game);
+                    if !previous.is_empty() {
+                        games.push(previous);
+                    }
+                    in_movetext = false;
+                }
+                let line: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if let Some((name, value)) = line.trim().split_once(char::is_whitespace) {
+                    let value = value.trim();
+                    let value = value.strip_prefix('"').unwrap_or(value);
+                    let value = value.strip_suffix('"').unwrap_or(value);
+                    let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+                    game.tags.push((name.to_string(), value));
+                }
+            }
+            '{' | ';' => {
+                let end = if c == '{' { '}' } else { '\n' };
+                let comment: String = chars.by_ref().take_while(|c| *c != end).collect();
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                let line = current_line(&mut game, &mut stack);
+                if !comment.is_empty() {
+                    line.comments.push((line.moves.len(), comment));
+                }
+                in_movetext = true;
+            }
+            '(' => {
+                let line = current_line(&mut game, &mut stack);
+                let start = line.moves.len();
+                stack.push((PgnGame::default(), start));
+            }
+            ')' => {
+                if let Some((variation, start)) = stack.pop() {
+                    let line = current_line(&mut game, &mut stack);
+                    line.variations.push((start, variation));
+                }
+            }
+            c if c.is_whitespace() => (),
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        push_token(current_line(&mut game, &mut stack), &token);
+    }
+    // Variations which were not closed
+    while let Some((variation, start)) = stack.pop() {
+        current_line(&mut game, &mut stack)
+            .variations
+            .push((start, variation));
+    }
+    if !game.is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+fn current_line<'a>(game: &'a mut PgnGame, stack: &'a mut [(PgnGame, usize)]) -> &'a mut PgnGame {
+    // The innermost variation which is being read
+    match stack.last_mut() {
+        Some((line, _)) => line,
+        None => game,
+    }
+}
+
+fn push_token(game: &mut PgnGame, token: &str) -> bool {
+    // Adds a movetext token to the game, returns true if the token ends the game
+    match token {
+        "1-0" | "0-1" | "1/2-1/2" | "*" => {
+            game.result = token.to_string();
+            return true;
+        }
+        _ => (),
+    }
+    // Numeric annotation glyphs
+    if let Some(nag) = token.strip_prefix('$') {
+        if let Ok(nag) = nag.parse::<u8>() {
+            game.nags.push((game.moves.len(), nag));
+        }
+        return false;
+    }
+    // Move numbers such as "12." or "12..." which can be glued to the move
+    let san = if token.starts_with("0-0") {
+        token
+    } else {
+        token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+    };
+    // Suffix annotations are stored as glyphs
+    let stripped = san.trim_end_matches(['!', '?']);
+    let nag = match &san[stripped.len()..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    if !stripped.is_empty() {
+        game.moves.push(stripped.to_string());
+        if let Some(nag) = nag {
+            game.nags.push((game.moves.len(), nag));
+        }
+    }
+    false
+}
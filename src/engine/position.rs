@@ -0,0 +1,643 @@
+use super::attacks::*;
+use super::fen::Fen;
+use chess::{Board, CastleRights, ChessMove, Color, Piece, ALL_SQUARES};
+use once_cell::sync::Lazy;
+use shakmaty::zobrist::{Zobrist64, ZobristValue};
+use shakmaty::CastlingSide;
+use std::fmt;
+use std::ops::Deref;
+
+// Bitboard position with its own move generation and make/unmake of moves
+// It gives the same moves and the same (Polyglot) hash as the chess crate board, see tests/perft.rs
+
+pub const MAX_MOVES: usize = 256;
+
+// Move flags, the promotion moves keep the promoted piece in the two low bits
+const QUIET: u16 = 0;
+const DOUBLE_PUSH: u16 = 1;
+const KING_CASTLE: u16 = 2;
+const QUEEN_CASTLE: u16 = 3;
+const CAPTURE: u16 = 4;
+const EN_PASSANT: u16 = 5;
+const PROMOTION: u16 = 8;
+
+const PROMOTIONS: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+// Pieces in the mailbox are color * 6 + piece index
+const NO_PIECE: u8 = 12;
+
+// Castling rights
+const WHITE_KING_SIDE: u8 = 1;
+const WHITE_QUEEN_SIDE: u8 = 2;
+const BLACK_KING_SIDE: u8 = 4;
+const BLACK_QUEEN_SIDE: u8 = 8;
+
+// The castling rights kept when a piece moves from or to a square
+const CASTLING_MASKS: [u8; 64] = {
+    let mut masks = [15; 64];
+    masks[0] = !WHITE_QUEEN_SIDE & 15;
+    masks[4] = !(WHITE_KING_SIDE | WHITE_QUEEN_SIDE) & 15;
+    masks[7] = !WHITE_KING_SIDE & 15;
+    masks[56] = !BLACK_QUEEN_SIDE & 15;
+    masks[60] = !(BLACK_KING_SIDE | BLACK_QUEEN_SIDE) & 15;
+    masks[63] = !BLACK_KING_SIDE & 15;
+    masks
+};
+
+struct Keys {
+    // The Polyglot random numbers, so the hash can be used to probe opening books
+    pieces: [[u64; 64]; 12],
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+    white_to_move: u64,
+}
+
+static KEYS: Lazy<Keys> = Lazy::new(|| {
+    let colors = [shakmaty::Color::White, shakmaty::Color::Black];
+    let roles = [
+        shakmaty::Role::Pawn,
+        shakmaty::Role::Knight,
+        shakmaty::Role::Bishop,
+        shakmaty::Role::Rook,
+        shakmaty::Role::Queen,
+        shakmaty::Role::King,
+    ];
+    let mut keys = Keys {
+        pieces: [[0; 64]; 12],
+        castling: [0; 4],
+        en_passant: [0; 8],
+        white_to_move: Zobrist64::zobrist_for_white_turn().0,
+    };
+    for (color_index, color) in colors.iter().enumerate() {
+        for (role_index, role) in roles.iter().enumerate() {
+            for square in 0..64 {
+                keys.pieces[color_index * 6 + role_index][square] = Zobrist64::zobrist_for_piece(
+                    shakmaty::Square::new(square as u32),
+                    shakmaty::Piece {
+                        color: *color,
+                        role: *role,
+                    },
+                )
+                .0;
+            }
+        }
+        for (side_index, side) in [CastlingSide::KingSide, CastlingSide::QueenSide]
+            .iter()
+            .enumerate()
+        {
+            keys.castling[color_index * 2 + side_index] =
+                Zobrist64::zobrist_for_castling_right(*color, *side).0;
+        }
+    }
+    for file in 0..8 {
+        keys.en_passant[file] =
+            Zobrist64::zobrist_for_en_passant_file(shakmaty::File::new(file as u32)).0;
+    }
+    keys
+});
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    fn new(from: usize, to: usize, flags: u16) -> Move {
+        Move(from as u16 | (to as u16) << 6 | flags << 12)
+    }
+
+    pub fn from(self) -> usize {
+        (self.0 & 63) as usize
+    }
+
+    pub fn to(self) -> usize {
+        (self.0 >> 6 & 63) as usize
+    }
+
+    fn flags(self) -> u16 {
+        self.0 >> 12
+    }
+
+    pub fn promotion(self) -> Option<Piece> {
+        match self.flags() & PROMOTION {
+            0 => None,
+            _ => Some(PROMOTIONS[(self.flags() & 3) as usize]),
+        }
+    }
+
+    pub fn is_capture(self) -> bool {
+        self.flags() & CAPTURE != 0
+    }
+
+    pub fn is_castle(self) -> bool {
+        matches!(self.flags(), KING_CASTLE | QUEEN_CASTLE)
+    }
+
+    pub fn to_chess_move(self) -> ChessMove {
+        ChessMove::new(
+            ALL_SQUARES[self.from()],
+            ALL_SQUARES[self.to()],
+            self.promotion(),
+        )
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_chess_move())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct MoveList<M = Move> {
+    // Fixed capacity list of moves which is kept on the stack
+    moves: [M; MAX_MOVES],
+    len: usize,
+}
+
+impl<M: Copy + Default> MoveList<M> {
+    pub fn new() -> MoveList<M> {
+        MoveList {
+            moves: [M::default(); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, mv: M) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn swap_remove(&mut self, index: usize) -> M {
+        let mv = self.moves[index];
+        self.len -= 1;
+        self.moves[index] = self.moves[self.len];
+        mv
+    }
+}
+
+impl<M: Copy + Default> Default for MoveList<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Deref for MoveList<M> {
+    type Target = [M];
+
+    fn deref(&self) -> &[M] {
+        &self.moves[..self.len]
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Undo {
+    // What a move changed which can't be computed back from the move itself
+    mv: Move,
+    captured: u8,
+    castling: u8,
+    en_passant: Option<usize>,
+    halfmove: u32,
+    hash: u64,
+}
+
+#[derive(Clone)]
+pub struct Position {
+    pieces: [u64; 6],
+    colors: [u64; 2],
+    mailbox: [u8; 64],
+    // 0 for white, 1 for black
+    side: usize,
+    castling: u8,
+    // The en passant target square, only kept if an enemy pawn could capture like in the chess crate
+    en_passant: Option<usize>,
+    halfmove: u32,
+    fullmove: u32,
+    hash: u64,
+    history: Vec<Undo>,
+}
+
+impl Position {
+    pub fn from_board(board: &Board, halfmove: u32, fullmove: u32) -> Position {
+        let mut position = Position {
+            pieces: [0; 6],
+            colors: [0; 2],
+            mailbox: [NO_PIECE; 64],
+            side: board.side_to_move().to_index(),
+            castling: 0,
+            en_passant: None,
+            halfmove,
+            fullmove,
+            hash: 0,
+            history: Vec::new(),
+        };
+        for square in ALL_SQUARES {
+            if let (Some(piece), Some(color)) = (board.piece_on(square), board.color_on(square)) {
+                position.put(
+                    square.to_index(),
+                    (color.to_index() * 6 + piece.to_index()) as u8,
+                );
+            }
+        }
+        for (color, king_side, queen_side) in [
+            (Color::White, WHITE_KING_SIDE, WHITE_QUEEN_SIDE),
+            (Color::Black, BLACK_KING_SIDE, BLACK_QUEEN_SIDE),
+        ] {
+            let rights = board.castle_rights(color);
+            if rights.has_kingside() {
+                position.castling |= king_side;
+            }
+            if rights.has_queenside() {
+                position.castling |= queen_side;
+            }
+        }
+        // The chess crate keeps the square of the pawn which can be taken
+        position.en_passant = board
+            .en_passant()
+            .map(|square| square.uforward(board.side_to_move()).to_index());
+
+        position.hash ^= position.castling_key(position.castling);
+        if let Some(square) = position.en_passant {
+            position.hash ^= KEYS.en_passant[square % 8];
+        }
+        if position.side == 0 {
+            position.hash ^= KEYS.white_to_move;
+        }
+        position
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Position, String> {
+        let fen = Fen::parse(fen)?;
+        Ok(Position::from_board(&fen.board, fen.halfmove, fen.fullmove))
+    }
+
+    pub fn to_board(&self) -> Board {
+        // The same position as a chess crate board
+        let mut builder = chess::BoardBuilder::new();
+        for square in ALL_SQUARES {
+            if let Some((color, piece)) = self.piece_on(square.to_index()) {
+                builder.piece(square, piece, color);
+            }
+        }
+        for (color, king_side, queen_side) in [
+            (Color::White, WHITE_KING_SIDE, WHITE_QUEEN_SIDE),
+            (Color::Black, BLACK_KING_SIDE, BLACK_QUEEN_SIDE),
+        ] {
+            let rights = match (self.castling & king_side, self.castling & queen_side) {
+                (0, 0) => CastleRights::NoRights,
+                (_, 0) => CastleRights::KingSide,
+                (0, _) => CastleRights::QueenSide,
+                _ => CastleRights::Both,
+            };
+            builder.castle_rights(color, rights);
+        }
+        builder.side_to_move(self.side_to_move());
+        builder.en_passant(self.en_passant.map(|square| ALL_SQUARES[square].get_file()));
+        Board::try_from(&builder).unwrap()
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        [Color::White, Color::Black][self.side]
+    }
+
+    pub fn halfmove(&self) -> u32 {
+        self.halfmove
+    }
+
+    pub fn fullmove(&self) -> u32 {
+        self.fullmove
+    }
+
+    pub fn piece_on(&self, square: usize) -> Option<(Color, Piece)> {
+        match self.mailbox[square] {
+            NO_PIECE => None,
+            piece => Some((
+                [Color::White, Color::Black][piece as usize / 6],
+                chess::ALL_PIECES[piece as usize % 6],
+            )),
+        }
+    }
+
+    pub fn in_check(&self) -> bool {
+        self.is_attacked(self.king_square(self.side), 1 - self.side)
+    }
+
+    pub fn compute_hash(&self) -> u64 {
+        // The hash computed from scratch, it has to be equal to the incrementally updated one
+        let mut hash = self.castling_key(self.castling);
+        for square in 0..64 {
+            let piece = self.mailbox[square];
+            if piece != NO_PIECE {
+                hash ^= KEYS.pieces[piece as usize][square];
+            }
+        }
+        if let Some(square) = self.en_passant {
+            hash ^= KEYS.en_passant[square % 8];
+        }
+        if self.side == 0 {
+            hash ^= KEYS.white_to_move;
+        }
+        hash
+    }
+
+    pub fn legal_moves(&mut self) -> MoveList {
+        // The pseudo-legal moves which don't leave the king in check
+        // Only king moves, en passant, moves of pinned pieces and moves out of check can do that,
+        // the last three are tested by playing them
+        let mut moves = self.pseudo_legal_moves();
+        let us = self.side;
+        let king = self.king_square(us);
+        let in_check = self.in_check();
+        let pinned = self.pinned(us);
+        let without_king = (self.colors[0] | self.colors[1]) & !(1 << king);
+        let mut index = 0;
+        while index < moves.len() {
+            let mv = moves[index];
+            let legal = if mv.from() == king {
+                mv.is_castle() || !self.is_attacked_through(mv.to(), 1 - us, without_king)
+            } else if in_check || mv.flags() == EN_PASSANT || pinned & 1 << mv.from() != 0 {
+                self.make_move(mv);
+                let legal = !self.is_attacked(king, self.side);
+                self.unmake_move();
+                legal
+            } else {
+                true
+            };
+            if legal {
+                index += 1;
+            } else {
+                moves.swap_remove(index);
+            }
+        }
+        moves
+    }
+
+    pub fn find_move(&mut self, chess_move: ChessMove) -> Option<Move> {
+        // The legal move with the same squares and promotion as a chess crate move
+        self.legal_moves().iter().copied().find(|mv| {
+            mv.from() == chess_move.get_source().to_index()
+                && mv.to() == chess_move.get_dest().to_index()
+                && mv.promotion() == chess_move.get_promotion()
+        })
+    }
+
+    pub fn pseudo_legal_moves(&self) -> MoveList {
+        // All the moves of the side to move except those which leave its king in check
+        // Castling through an attacked square is never generated
+        let mut moves = MoveList::new();
+        let us = self.side;
+        let them = 1 - us;
+        let occupied = self.colors[0] | self.colors[1];
+        let own = self.colors[us];
+        let enemy = self.colors[them];
+
+        let (forward, start_rank, last_rank): (i32, usize, usize) =
+            if us == 0 { (8, 1, 7) } else { (-8, 6, 0) };
+        for from in squares(self.pieces[0] & own) {
+            let to = (from as i32 + forward) as usize;
+            if occupied & 1 << to == 0 {
+                if to / 8 == last_rank {
+                    push_promotions(&mut moves, from, to, 0);
+                } else {
+                    moves.push(Move::new(from, to, QUIET));
+                    let double = (to as i32 + forward) as usize;
+                    if from / 8 == start_rank && occupied & 1 << double == 0 {
+                        moves.push(Move::new(from, double, DOUBLE_PUSH));
+                    }
+                }
+            }
+            for to in squares(pawn_attacks(us, from) & enemy) {
+                if to / 8 == last_rank {
+                    push_promotions(&mut moves, from, to, CAPTURE);
+                } else {
+                    moves.push(Move::new(from, to, CAPTURE));
+                }
+            }
+            if let Some(to) = self.en_passant {
+                if pawn_attacks(us, from) & 1 << to != 0 {
+                    moves.push(Move::new(from, to, EN_PASSANT));
+                }
+            }
+        }
+
+        for piece in 1..6 {
+            for from in squares(self.pieces[piece] & own) {
+                let attacks = match piece {
+                    1 => knight_attacks(from),
+                    2 => bishop_attacks(from, occupied),
+                    3 => rook_attacks(from, occupied),
+                    4 => queen_attacks(from, occupied),
+                    _ => king_attacks(from),
+                };
+                for to in squares(attacks & !own) {
+                    let flags = if enemy & 1 << to != 0 { CAPTURE } else { QUIET };
+                    moves.push(Move::new(from, to, flags));
+                }
+            }
+        }
+
+        let (king_side, queen_side, king) = if us == 0 {
+            (WHITE_KING_SIDE, WHITE_QUEEN_SIDE, 4)
+        } else {
+            (BLACK_KING_SIDE, BLACK_QUEEN_SIDE, 60)
+        };
+        if self.castling & (king_side | queen_side) != 0 && !self.is_attacked(king, them) {
+            if self.castling & king_side != 0
+                && occupied & (0b11 << (king + 1)) == 0
+                && !self.is_attacked(king + 1, them)
+                && !self.is_attacked(king + 2, them)
+            {
+                moves.push(Move::new(king, king + 2, KING_CASTLE));
+            }
+            if self.castling & queen_side != 0
+                && occupied & (0b111 << (king - 3)) == 0
+                && !self.is_attacked(king - 1, them)
+                && !self.is_attacked(king - 2, them)
+            {
+                moves.push(Move::new(king, king - 2, QUEEN_CASTLE));
+            }
+        }
+        moves
+    }
+
+    pub fn make_move(&mut self, mv: Move) {
+        // Plays a pseudo-legal move, it can be taken back with unmake_move
+        let us = self.side;
+        let them = 1 - us;
+        let (from, to, flags) = (mv.from(), mv.to(), mv.flags());
+        let piece = self.mailbox[from];
+        let captured_square = match flags {
+            EN_PASSANT => (to as i32 - if us == 0 { 8 } else { -8 }) as usize,
+            _ => to,
+        };
+        let captured = self.mailbox[captured_square];
+        self.history.push(Undo {
+            mv,
+            captured,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove: self.halfmove,
+            hash: self.hash,
+        });
+
+        if let Some(square) = self.en_passant.take() {
+            self.hash ^= KEYS.en_passant[square % 8];
+        }
+        if captured != NO_PIECE {
+            self.remove(captured_square);
+        }
+        self.remove(from);
+        match mv.promotion() {
+            Some(promotion) => self.put(to, (us * 6 + promotion.to_index()) as u8),
+            None => self.put(to, piece),
+        }
+        match flags {
+            KING_CASTLE => self.move_piece(to + 1, to - 1),
+            QUEEN_CASTLE => self.move_piece(to - 2, to + 1),
+            _ => (),
+        }
+
+        let castling = self.castling & CASTLING_MASKS[from] & CASTLING_MASKS[to];
+        self.hash ^= self.castling_key(self.castling ^ castling);
+        self.castling = castling;
+
+        let pawn_move = chess::ALL_PIECES[piece as usize % 6] == Piece::Pawn;
+        self.halfmove = if pawn_move || captured != NO_PIECE {
+            0
+        } else {
+            self.halfmove + 1
+        };
+        if flags == DOUBLE_PUSH {
+            let square = (from + to) / 2;
+            if pawn_attacks(us, square) & self.pieces[0] & self.colors[them] != 0 {
+                self.en_passant = Some(square);
+                self.hash ^= KEYS.en_passant[square % 8];
+            }
+        }
+        if us == 1 {
+            self.fullmove += 1;
+        }
+        self.side = them;
+        self.hash ^= KEYS.white_to_move;
+    }
+
+    pub fn unmake_move(&mut self) {
+        // Takes back the last move played with make_move
+        let undo = match self.history.pop() {
+            Some(undo) => undo,
+            None => return,
+        };
+        let us = 1 - self.side;
+        self.side = us;
+        if us == 1 {
+            self.fullmove -= 1;
+        }
+        let mv = undo.mv;
+        let (from, to, flags) = (mv.from(), mv.to(), mv.flags());
+        match flags {
+            KING_CASTLE => self.move_piece(to - 1, to + 1),
+            QUEEN_CASTLE => self.move_piece(to + 1, to - 2),
+            _ => (),
+        }
+        match mv.promotion() {
+            Some(_) => {
+                self.remove(to);
+                self.put(from, (us * 6) as u8);
+            }
+            None => self.move_piece(to, from),
+        }
+        if undo.captured != NO_PIECE {
+            let captured_square = match flags {
+                EN_PASSANT => (to as i32 - if us == 0 { 8 } else { -8 }) as usize,
+                _ => to,
+            };
+            self.put(captured_square, undo.captured);
+        }
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove = undo.halfmove;
+        self.hash = undo.hash;
+    }
+
+    fn king_square(&self, color: usize) -> usize {
+        (self.pieces[5] & self.colors[color]).trailing_zeros() as usize
+    }
+
+    fn pinned(&self, color: usize) -> u64 {
+        // The pieces of the color which are the only piece between their king and an enemy slider
+        let king = self.king_square(color);
+        let enemy = self.colors[1 - color];
+        let snipers = (rook_attacks(king, 0) & (self.pieces[3] | self.pieces[4])
+            | bishop_attacks(king, 0) & (self.pieces[2] | self.pieces[4]))
+            & enemy;
+        let occupied = self.colors[0] | self.colors[1];
+        squares(snipers)
+            .map(|sniper| between(king, sniper) & occupied)
+            .filter(|blockers| blockers.count_ones() == 1)
+            .fold(0, |pinned, blockers| pinned | blockers & self.colors[color])
+    }
+
+    fn is_attacked(&self, square: usize, by: usize) -> bool {
+        self.is_attacked_through(square, by, self.colors[0] | self.colors[1])
+    }
+
+    fn is_attacked_through(&self, square: usize, by: usize, occupied: u64) -> bool {
+        // Whether the square is attacked with the sliders' rays blocked by the given pieces
+        let attackers = self.colors[by];
+        let diagonal = (self.pieces[2] | self.pieces[4]) & attackers;
+        let straight = (self.pieces[3] | self.pieces[4]) & attackers;
+        // A pawn of the other color on the square would attack the pawns which attack it
+        pawn_attacks(1 - by, square) & self.pieces[0] & attackers != 0
+            || knight_attacks(square) & self.pieces[1] & attackers != 0
+            || king_attacks(square) & self.pieces[5] & attackers != 0
+            || bishop_attacks(square, occupied) & diagonal != 0
+            || rook_attacks(square, occupied) & straight != 0
+    }
+
+    fn castling_key(&self, rights: u8) -> u64 {
+        (0..4)
+            .filter(|bit| rights & 1 << bit != 0)
+            .fold(0, |key, bit| key ^ KEYS.castling[bit])
+    }
+
+    fn put(&mut self, square: usize, piece: u8) {
+        self.pieces[piece as usize % 6] |= 1 << square;
+        self.colors[piece as usize / 6] |= 1 << square;
+        self.mailbox[square] = piece;
+        self.hash ^= KEYS.pieces[piece as usize][square];
+    }
+
+    fn remove(&mut self, square: usize) {
+        let piece = self.mailbox[square];
+        self.pieces[piece as usize % 6] &= !(1 << square);
+        self.colors[piece as usize / 6] &= !(1 << square);
+        self.mailbox[square] = NO_PIECE;
+        self.hash ^= KEYS.pieces[piece as usize][square];
+    }
+
+    fn move_piece(&mut self, from: usize, to: usize) {
+        let piece = self.mailbox[from];
+        self.remove(from);
+        self.put(to, piece);
+    }
+}
+
+fn push_promotions(moves: &mut MoveList, from: usize, to: usize, flags: u16) {
+    for promotion in 0..4 {
+        moves.push(Move::new(from, to, flags | PROMOTION | promotion));
+    }
+}
+
+fn squares(mut bitboard: u64) -> impl Iterator<Item = usize> {
+    // The squares of a bitboard from a1 to h8
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let square = bitboard.trailing_zeros() as usize;
+        bitboard &= bitboard - 1;
+        Some(square)
+    })
+}
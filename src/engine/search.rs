@@ -1,53 +1,298 @@
+use super::chess960::CastlingRooks;
 use super::evaluation::*;
 use super::helpers::*;
+use super::movegen::{CopyMake, MoveGenerator};
+use super::movepick::*;
+use super::nnue::Nnue;
+use super::tablebase::Tablebase;
+use super::tunables::*;
+use super::weights::Weights;
 
 use chess::{Board, BoardStatus, CacheTable, ChessMove, Color, MoveGen};
 use rand::prelude::*;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use std::io;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Default, PartialEq, PartialOrd)]
+struct HashEntry {
+    // Score from white's point of view and the best move found in the position
+    score: i32,
+    best_move: Option<ChessMove>,
+    // Entries written before the last clear_hash have an older generation and are ignored
+    generation: u8,
+}
 
 pub struct Engine {
     // Engine structure
-    random: ThreadRng,
-    cache: CacheTable<i32>,
+    random: StdRng,
+    cache: CacheTable<HashEntry>,
+    cache_size: usize,
+    generation: u8,
+    tablebase: Tablebase,
+    evaluator: Box<dyn Evaluator>,
+    // Weights of the classical evaluation, kept when switching between backends
+    weights: Weights,
+    params: SearchParams,
+    // Number of positions visited since the last reset
+    nodes: u64,
+    // The search stops inside the tree once this many nodes are visited or the deadline has passed
+    node_limit: Option<u64>,
+    deadline: Option<Instant>,
+    stopped: bool,
+    // Quiet moves which caused a beta cutoff, two per ply
+    killers: [[Option<ChessMove>; 2]; MAX_PLY],
+    history: History,
+    // Chess960 castling rights of the position being searched, the Board doesn't have them
+    castling: CastlingRooks,
+    // Chess crate backends for the root and the positions after a Chess960 castle
+    // They are kept between the searches so their stacks don't have to be allocated again
+    spare: Vec<CopyMake>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Engine {
     pub fn new() -> Engine {
         // Object generator
+        Engine::with_cache_size(268_435_456)
+    }
+
+    pub fn with_cache_size(size: usize) -> Engine {
+        // Creates an engine with a smaller or bigger hash, the size has to be a power of two
         Engine {
-            random: thread_rng(),
-            cache: CacheTable::new(268_435_456, 0),
+            random: StdRng::from_entropy(),
+            cache: CacheTable::new(size, HashEntry::default()),
+            cache_size: size,
+            generation: 0,
+            tablebase: Tablebase::new(),
+            evaluator: Box::new(Classical::default()),
+            weights: Weights::default(),
+            params: SearchParams::default(),
+            nodes: 0,
+            node_limit: None,
+            deadline: None,
+            stopped: false,
+            killers: [[None; 2]; MAX_PLY],
+            history: History::new(),
+            castling: CastlingRooks::default(),
+            spare: Vec::new(),
         }
     }
 
+    pub fn nodes(&self) -> u64 {
+        // Returns the number of positions visited since the last reset
+        self.nodes
+    }
+
+    pub fn reset_nodes(&mut self) {
+        self.nodes = 0;
+    }
+
+    pub fn set_node_limit(&mut self, nodes: Option<u64>) {
+        // Stops the searches once the node count reaches the limit, they return None when it happens
+        self.node_limit = nodes;
+    }
+
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        // Stops the searches once the time has passed, they return None when it happens
+        self.deadline = deadline;
+    }
+
+    pub fn stopped(&self) -> bool {
+        // Whether the last search was stopped by one of the limits
+        self.stopped
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        // Makes the random cushion of the root moves reproducible
+        self.random = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn clear_hash(&mut self) {
+        // Forgets every stored score, they are stale once the evaluation changes
+        // Only the generation is bumped, the table is rebuilt when the counter wraps around
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.cache = CacheTable::new(self.cache_size, HashEntry::default());
+        }
+    }
+
+    pub fn set_castling(&mut self, castling: CastlingRooks) {
+        // Sets the Chess960 castling rights of the positions searched next
+        // The search then plays the castling moves itself, written as the king taking the rook
+        self.castling = castling;
+    }
+
+    pub fn set_syzygy_path(&mut self, path: &str) -> io::Result<usize> {
+        // Loads the Syzygy tablebases from the given directories
+        self.tablebase.set_path(path)
+    }
+
+    pub fn set_eval_file(&mut self, path: &str) -> io::Result<()> {
+        // Loads an NNUE network, the classical evaluation is used if the path is empty or the loading fails
+        self.evaluator = Box::new(Classical::new(self.weights.clone()));
+        self.clear_hash();
+        if path.is_empty() || path == "<empty>" {
+            return Ok(());
+        }
+        self.evaluator = Box::new(Nnue::load(path)?);
+        Ok(())
+    }
+
+    pub fn set_weights(&mut self, weights: Weights) {
+        // Sets the weights of the classical evaluation
+        self.evaluator.set_weights(&weights);
+        self.weights = weights;
+        self.clear_hash();
+    }
+
+    pub fn set_tunable(&mut self, name: &str, value: i32) -> bool {
+        // Changes one of the search or evaluation constants listed in TUNABLES
+        // Returns false if there is no tunable with the given name
+        let tunable = match find_tunable(name) {
+            Some(tunable) => tunable,
+            None => return false,
+        };
+        match tunable.name {
+            // The stored scores were found with the old value
+            "QsearchCheckDepth" => {
+                self.params.qsearch_check_depth = value;
+                self.clear_hash();
+            }
+            "RandomMargin" => {
+                self.params.random_margin = value;
+                self.clear_hash();
+            }
+            // set_weights clears the hash itself
+            _ => {
+                let mut weights = self.weights.clone();
+                match weights
+                    .scalars_mut()
+                    .into_iter()
+                    .find(|(x, _)| camel_case(x) == tunable.name)
+                {
+                    Some((_, target)) => *target = value,
+                    None => return false,
+                }
+                self.set_weights(weights);
+            }
+        }
+        true
+    }
+
+    pub fn tunable(&self, name: &str) -> Option<i32> {
+        // Current value of one of the constants listed in TUNABLES
+        match find_tunable(name)?.name {
+            "QsearchCheckDepth" => Some(self.params.qsearch_check_depth),
+            "RandomMargin" => Some(self.params.random_margin),
+            name => self
+                .weights
+                .scalars()
+                .into_iter()
+                .find(|(x, _)| camel_case(x) == name)
+                .map(|(_, value)| value),
+        }
+    }
 
     pub fn play(&mut self, board: &Board, depth: i32) -> Option<ChessMove> {
         // Calls the necessary function to return the best move
-        self.negamax_root(board, depth)
+        self.search(board, depth).map(|(mv, _)| mv)
+    }
+
+    pub fn search(&mut self, board: &Board, depth: i32) -> Option<(ChessMove, i32)> {
+        // Returns the best move together with its score relative to the side to move
+        let mut position = self.copy_make(*board);
+        let result = self.negamax_root(&mut position, depth);
+        self.spare.push(position);
+        result
     }
 
+    pub fn search_with<G: MoveGenerator>(
+        &mut self,
+        position: &mut G,
+        depth: i32,
+    ) -> Option<(ChessMove, i32)> {
+        // search with any of the move generation backends, the moves are played with make and unmake
+        // The backends visit the same nodes and return the same move and score
+        self.negamax_root(position, depth)
+    }
+
+    pub fn quiescence(&mut self, board: &Board) -> i32 {
+        // Runs only the quiescence search with a full window, the score is relative to the side to move
+        self.evaluator.reset(board);
+        self.stopped = false;
+        let mut position = self.copy_make(*board);
+        let score = self.quiescence_search(&mut position, -999_999, 999_999, 0);
+        self.spare.push(position);
+        score
+    }
 
-    fn negamax_root(&mut self, board: &Board, depth: i32) -> Option<ChessMove> {
+    fn negamax_root<G: MoveGenerator>(
+        &mut self,
+        position: &mut G,
+        depth: i32,
+    ) -> Option<(ChessMove, i32)> {
         // Root function to the negamax
-        // Returns the best move selected from recursive calls to the negamax function
+        // Returns the best move selected from recursive calls to the negamax function and its score
+        let board = position.to_board();
+        let rooks = self.castling;
+        let mut castles = rooks.castles(&board).into_iter();
         match board.status() {
             BoardStatus::Checkmate => {
                 println!("checkmate");
                 return None;
             }
-            BoardStatus::Stalemate => {
+            // The chess crate doesn't see the Chess960 castling moves
+            BoardStatus::Stalemate if castles.len() == 0 => {
                 println!("stalemate");
                 return None;
             }
-            BoardStatus::Ongoing => (),
+            _ => (),
         }
 
+        // Positions in the tablebases don't need to be searched, the tables have no castling rights
+        if rooks.is_empty() {
+            if let Some(mv) = self.tablebase.best_move(&board) {
+                let score = self.tablebase.probe_wdl(&board).unwrap_or(0);
+                return Some((mv, score));
+            }
+        }
+
+        self.evaluator.reset(&board);
+        self.stopped = false;
+        self.killers = [[None; 2]; MAX_PLY];
+        self.history.age();
         let mut best_move: Option<ChessMove> = None;
         let mut alpha = -999_999;
-        for mv in reorder_moves(&board, MoveGen::new_legal(&board)) {
-            let mut eval = -self.negamax(&board.make_move_new(mv), -999_999, 999_999, depth - 1);
+        let mut picker = MovePicker::new(&board, self.hash_move(&board), [None; 2]);
+        // The Chess960 castling moves come after the other moves
+        loop {
+            let (mv, mut eval) = if let Some(mv) = picker.next(&board, &self.history) {
+                let eval = self.search_move(position, &board, mv, |engine, child| {
+                    -engine.negamax(child, -999_999, 999_999, depth - 1, 1)
+                });
+                (mv, eval)
+            } else if let Some((mv, new_board)) = castles.next() {
+                let eval = self.search_castle(&board, mv, new_board, |engine, child| {
+                    -engine.negamax(child, -999_999, 999_999, depth - 1, 1)
+                });
+                (mv, eval)
+            } else {
+                break;
+            };
+            // The scores of an unfinished search can't be trusted
+            if self.stopped {
+                return None;
+            }
             // Adds a little random cushion to the moves evaluation so the selection is randomised between similarly evaluated moves
-            let random = self.random.gen_range(-1..=1);
+            let random = self
+                .random
+                .gen_range(-self.params.random_margin..=self.params.random_margin);
             eval += random;
             if eval > alpha {
                 alpha = eval;
@@ -55,47 +300,122 @@ impl Engine {
             }
         }
         // It is uselles to save the hash of the best move because it won't be ever used
-        best_move
+        best_move.map(|mv| (mv, alpha))
     }
 
-    fn negamax(&mut self, board: &Board, mut alpha: i32, beta: i32, depth: i32) -> i32 {
+    fn negamax<G: MoveGenerator>(
+        &mut self,
+        position: &mut G,
+        mut alpha: i32,
+        beta: i32,
+        depth: i32,
+        ply: usize,
+    ) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        let board = position.to_board();
+        let rooks = self.castling;
+        let mut castles = rooks.castles(&board).into_iter();
         // The <= will make the program to act as depth=1 when depth<1
         if depth <= 0
             || match board.status() {
                 BoardStatus::Checkmate => true,
-                BoardStatus::Stalemate => true,
+                BoardStatus::Stalemate => castles.len() == 0,
                 BoardStatus::Ongoing => false,
             }
         {
-            return self.quiescence_search(board, alpha, beta, 0);
+            return self.quiescence_search(position, alpha, beta, 0);
         }
+        self.nodes += 1;
 
-        for mv in reorder_moves(&board, MoveGen::new_legal(&board)) {
-            let eval = -self.negamax(&board.make_move_new(mv), -beta, -alpha, depth - 1);
+        // The piece count is checked first so most nodes don't convert the board at all
+        if rooks.is_empty() && self.tablebase.covers(&board) {
+            if let Some(score) = self.tablebase.probe_wdl(&board) {
+                return score;
+            }
+        }
+
+        let ply = ply.min(MAX_PLY - 1);
+        let mut best_move = None;
+        let mut picker = MovePicker::new(&board, self.hash_move(&board), self.killers[ply]);
+        loop {
+            let (mv, eval) = if let Some(mv) = picker.next(&board, &self.history) {
+                let eval = self.search_move(position, &board, mv, |engine, child| {
+                    -engine.negamax(child, -beta, -alpha, depth - 1, ply + 1)
+                });
+                (mv, eval)
+            } else if let Some((mv, new_board)) = castles.next() {
+                let eval = self.search_castle(&board, mv, new_board, |engine, child| {
+                    -engine.negamax(child, -beta, -alpha, depth - 1, ply + 1)
+                });
+                (mv, eval)
+            } else {
+                break;
+            };
+            if self.stopped {
+                return 0;
+            }
             if eval > alpha {
                 alpha = eval;
+                best_move = Some(mv);
             }
 
             if alpha >= beta {
+                // Quiet moves refuting the opponent's move are tried early in the sibling positions
+                if is_quiet(&board, mv) {
+                    if self.killers[ply][0] != Some(mv) {
+                        self.killers[ply] = [Some(mv), self.killers[ply][0]];
+                    }
+                    self.history.update(board.side_to_move(), mv, depth);
+                }
                 break;
             }
         }
-        self.save_hash(board, alpha);
+        self.save_hash(&board, alpha, best_move);
         alpha
-
     }
 
-    fn quiescence_search(&mut self, board: &Board, mut alpha: i32, beta: i32, depth: i32) -> i32 {
+    fn quiescence_search<G: MoveGenerator>(
+        &mut self,
+        position: &mut G,
+        mut alpha: i32,
+        beta: i32,
+        depth: i32,
+    ) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+        let board = position.to_board();
         // score is not interchangable with eval
-        let score = match self.probe_hash(board) {
+        let score = match self.probe_hash(&board) {
             Some(x) => x,
-            None => {
-                let score = evaluation(board, alpha, beta);
-                score
-            }
+            None => self.evaluator.evaluate(&board, alpha, beta),
         };
-        if board.status() != BoardStatus::Ongoing {
-            return score;
+        let rooks = self.castling;
+        match board.status() {
+            BoardStatus::Ongoing => (),
+            // The chess crate doesn't see the Chess960 castling moves, if they are the only legal
+            // moves the position isn't a stalemate and there is no static score to stand on
+            BoardStatus::Stalemate if !rooks.castles(&board).is_empty() => {
+                let mut best_score = -999_999;
+                for (mv, new_board) in rooks.castles(&board) {
+                    let eval = self.search_castle(&board, mv, new_board, |engine, child| {
+                        -engine.quiescence_search(child, -beta, -alpha, depth + 1)
+                    });
+                    if self.stopped {
+                        return 0;
+                    }
+                    if eval >= beta {
+                        return beta;
+                    }
+                    alpha = alpha.max(eval);
+                    best_score = best_score.max(eval);
+                }
+                return best_score;
+            }
+            _ => return score,
         }
         let mut best_score = score;
         if score >= beta {
@@ -104,8 +424,19 @@ impl Engine {
         if score > alpha {
             alpha = score
         }
-        for mv in filter_moves(board, MoveGen::new_legal(&board), depth) {
-            let eval = -self.quiescence_search(&board.make_move_new(mv), -beta, -alpha, depth + 1);
+        let moves = filter_moves(
+            &board,
+            MoveGen::new_legal(&board),
+            depth,
+            self.params.qsearch_check_depth,
+        );
+        for &mv in moves.iter() {
+            let eval = self.search_move(position, &board, mv, |engine, child| {
+                -engine.quiescence_search(child, -beta, -alpha, depth + 1)
+            });
+            if self.stopped {
+                return 0;
+            }
             if eval >= beta {
                 return beta;
             }
@@ -116,33 +447,125 @@ impl Engine {
                 best_score = eval;
             }
         }
-        self.save_hash(board, alpha);
+        self.save_hash(&board, alpha, None);
         best_score
     }
 
+    fn search_move<G: MoveGenerator>(
+        &mut self,
+        position: &mut G,
+        board: &Board,
+        mv: ChessMove,
+        search: impl FnOnce(&mut Engine, &mut G) -> i32,
+    ) -> i32 {
+        // Plays a legal move on the backend, searches the new position and takes the move back
+        let rooks = self.castling;
+        let backend_move = position
+            .find_move(mv)
+            .expect("the backend doesn't have a legal move of the chess crate");
+        position.make_move(backend_move);
+        self.castling = rooks.after(board, mv);
+        self.evaluator.make_move(board, &position.to_board());
+        let eval = search(self, position);
+        self.evaluator.unmake_move();
+        self.castling = rooks;
+        position.unmake_move();
+        eval
+    }
+
+    fn search_castle(
+        &mut self,
+        board: &Board,
+        mv: ChessMove,
+        new_board: Board,
+        search: impl FnOnce(&mut Engine, &mut CopyMake) -> i32,
+    ) -> i32 {
+        // Searches the position after a Chess960 castling move
+        // The backends can't play these moves, only a chess crate board holds the position after them
+        let rooks = self.castling;
+        self.castling = rooks.after(board, mv);
+        self.evaluator.make_move(board, &new_board);
+        let mut position = self.copy_make(new_board);
+        let eval = search(self, &mut position);
+        self.spare.push(position);
+        self.evaluator.unmake_move();
+        self.castling = rooks;
+        eval
+    }
+
+    fn copy_make(&mut self, board: Board) -> CopyMake {
+        // A chess crate backend starting from the given board, a spare one is reused if there is any
+        match self.spare.pop() {
+            Some(mut position) => {
+                position.set_board(board);
+                position
+            }
+            None => CopyMake::new(board),
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        // Checks the limits of the search, once it is stopped every node returns right away
+        if !self.stopped {
+            // The clock is only read every 1024 nodes
+            self.stopped = self.node_limit.is_some_and(|limit| self.nodes >= limit)
+                || (self.nodes.is_multiple_of(1024)
+                    && self
+                        .deadline
+                        .is_some_and(|deadline| Instant::now() >= deadline));
+        }
+        self.stopped
+    }
+
     pub fn probe_hash(&self, board: &Board) -> Option<i32> {
         // Returns the value in the hash for a given board
-        let hash = board.get_hash();
-        match self.cache.get(hash) {
+        match self.entry(board) {
             None => None,
             Some(x) => {
                 if board.side_to_move() == Color::White {
-                    Some(x)
+                    Some(x.score)
                 } else {
-                    Some(-x)
+                    Some(-x.score)
                 }
             }
         }
     }
 
-    fn save_hash(&mut self, board: &Board, eval: i32) {
+    fn hash_move(&self, board: &Board) -> Option<ChessMove> {
+        // Returns the best move stored in the hash for a given board
+        self.entry(board).and_then(|entry| entry.best_move)
+    }
+
+    fn hash_key(&self, board: &Board) -> u64 {
+        // The Chess960 castling rights are added to the hash of the board
+        // The chess crate's key is used with every backend so they share the entries and search the same tree
+        board.get_hash() ^ self.castling.key()
+    }
+
+    fn entry(&self, board: &Board) -> Option<HashEntry> {
+        // Returns the entry stored for a given board since the last clear_hash
+        self.cache
+            .get(self.hash_key(board))
+            .filter(|entry| entry.generation == self.generation)
+    }
+
+    fn save_hash(&mut self, board: &Board, eval: i32, best_move: Option<ChessMove>) {
         // Saves a board and its evaluation in the hash
-        let hash = board.get_hash();
-        let entry = if board.side_to_move() == Color::White {
+        // Without a new best move the one already stored for the board is kept
+        let hash = self.hash_key(board);
+        let score = if board.side_to_move() == Color::White {
             eval
         } else {
             -eval
         };
-        self.cache.add(hash, entry);
+        let best_move = best_move.or_else(|| self.hash_move(board));
+        self.cache.add(
+            hash,
+            HashEntry {
+                score,
+                best_move,
+                generation: self.generation,
+            },
+        );
     }
 }
@@ -0,0 +1,119 @@
+use chess::{BitBoard, Board, ChessMove, Color, MoveGen, Piece};
+use shakmaty::{Bitboard, ByColor, ByRole, CastlingMode, Chess, FromSetup, Setup};
+use shakmaty_syzygy::{Syzygy, Tablebase as Tables, Wdl};
+use std::env;
+use std::io;
+
+// Score of a tablebase win, lower than a found checkmate but higher than any evaluation
+pub const TB_WIN: i32 = 20_000;
+
+pub struct Tablebase {
+    // Syzygy tables loaded from the directories given by the SyzygyPath option
+    tables: Tables<Chess>,
+}
+
+impl Default for Tablebase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tablebase {
+    pub fn new() -> Tablebase {
+        Tablebase {
+            tables: Tables::new(),
+        }
+    }
+
+    pub fn set_path(&mut self, path: &str) -> io::Result<usize> {
+        // Replaces the loaded tables with the ones found in the given directories
+        // Multiple directories are separated the same way as in the PATH variable
+        // Returns the number of table files found
+        self.tables = Tables::new();
+        let mut count = 0;
+        if path.is_empty() || path == "<empty>" {
+            return Ok(count);
+        }
+        for directory in env::split_paths(path) {
+            count += self.tables.add_directory(directory)?;
+        }
+        Ok(count)
+    }
+
+    pub fn covers(&self, board: &Board) -> bool {
+        // Checks whether the position has few enough pieces to be in the tables
+        let pieces = board.combined().popcnt() as usize;
+        self.tables.max_pieces() > 0
+            && pieces <= self.tables.max_pieces()
+            && pieces <= Chess::MAX_PIECES
+    }
+
+    pub fn probe_wdl(&self, board: &Board) -> Option<i32> {
+        // Returns the tablebase score relative to the side to move
+        // The halfmove clock isn't tracked by the board so the position is treated as if a capture or a pawn move was just played
+        // Wins and losses which can be saved by the 50 moves rule are scored as draws
+        if !self.covers(board) {
+            return None;
+        }
+        let position = to_position(board)?;
+        match self.tables.probe_wdl_after_zeroing(&position).ok()? {
+            Wdl::Win => Some(TB_WIN),
+            Wdl::Loss => Some(-TB_WIN),
+            Wdl::CursedWin | Wdl::BlessedLoss | Wdl::Draw => Some(0),
+        }
+    }
+
+    pub fn best_move(&self, board: &Board) -> Option<ChessMove> {
+        // Returns the DTZ-optimal move if the position is in the tables
+        if !self.covers(board) {
+            return None;
+        }
+        let position = to_position(board)?;
+        let (best, _) = self.tables.best_move(&position).ok()??;
+        let uci = best.to_uci(CastlingMode::Standard).to_string();
+        MoveGen::new_legal(board).find(|mv| mv.to_string() == uci)
+    }
+}
+
+fn to_position(board: &Board) -> Option<Chess> {
+    // Converts the "chess" crate board into the board used by the tablebase crate
+    // Both crates number the squares from a1 to h8 so the bitboards are copied as they are
+    let bitboard = |x: &BitBoard| Bitboard(x.0);
+    let by_role = ByRole {
+        pawn: bitboard(board.pieces(Piece::Pawn)),
+        knight: bitboard(board.pieces(Piece::Knight)),
+        bishop: bitboard(board.pieces(Piece::Bishop)),
+        rook: bitboard(board.pieces(Piece::Rook)),
+        queen: bitboard(board.pieces(Piece::Queen)),
+        king: bitboard(board.pieces(Piece::King)),
+    };
+    let by_color = ByColor {
+        white: bitboard(board.color_combined(Color::White)),
+        black: bitboard(board.color_combined(Color::Black)),
+    };
+    // The castling rights are kept so the probe fails instead of ignoring them
+    let mut castling_rights = 0;
+    for (color, rank) in [(Color::White, 0), (Color::Black, 56)] {
+        let rights = board.castle_rights(color);
+        if rights.has_kingside() {
+            castling_rights |= 1 << (rank + 7);
+        }
+        if rights.has_queenside() {
+            castling_rights |= 1 << rank;
+        }
+    }
+    let setup = Setup {
+        board: shakmaty::Board::try_from_bitboards(by_role, by_color).ok()?,
+        turn: match board.side_to_move() {
+            Color::White => shakmaty::Color::White,
+            Color::Black => shakmaty::Color::Black,
+        },
+        castling_rights: Bitboard(castling_rights),
+        // The board keeps the square of the pawn which can be taken, the setup the square behind it
+        ep_square: board.en_passant().map(|square| {
+            shakmaty::Square::new(square.uforward(board.side_to_move()).to_int() as u32)
+        }),
+        ..Setup::empty()
+    };
+    Chess::from_setup(setup, CastlingMode::Standard).ok()
+}
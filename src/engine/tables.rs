@@ -100,3 +100,26 @@ pub const KING_TABLE_ENDGAME: [i32; 64] = [
     -50, -20, 20, 20, 20, 20, -20, -50, -50, -20, -20, -20, -20, -20, -20, -50, -50, -50, -50, -50,
     -50, -50, -50, -50,
 ];
+
+pub const PAWN_VALUE: i32 = 100;
+pub const KNIGHT_VALUE: i32 = 300;
+pub const BISHOP_VALUE: i32 = 310;
+pub const ROOK_VALUE: i32 = 500;
+pub const QUEEN_VALUE: i32 = 900;
+
+pub const CENTER_CONTROL: i32 = 5;
+pub const BISHOP_MOBILITY: i32 = 3;
+pub const KNIGHT_MOBILITY: i32 = 5;
+pub const ROOK_MOBILITY: i32 = 3;
+pub const QUEEN_MOBILITY: i32 = 1;
+pub const LAZY_MARGIN: i32 = 80;
+
+pub const BISHOP_PAIR: i32 = 30;
+pub const ROOK_OPEN_FILE: i32 = 20;
+pub const ROOK_HALF_OPEN_FILE: i32 = 10;
+pub const ROOK_SEVENTH_RANK: i32 = 20;
+pub const KNIGHT_OUTPOST: i32 = 20;
+pub const BISHOP_OUTPOST: i32 = 10;
+pub const BAD_BISHOP: i32 = 3;
+pub const TRAPPED_BISHOP: i32 = 100;
+pub const TRAPPED_ROOK: i32 = 50;
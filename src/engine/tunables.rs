@@ -0,0 +1,102 @@
+use super::tables::*;
+
+// Search constants which can be changed at runtime
+// Checks are searched in the quiescence search only up to this depth
+pub const QSEARCH_CHECK_DEPTH: i32 = 10;
+// Root moves get a random bonus up to this size so similar moves are picked randomly
+pub const RANDOM_MARGIN: i32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchParams {
+    pub qsearch_check_depth: i32,
+    pub random_margin: i32,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        SearchParams {
+            qsearch_check_depth: QSEARCH_CHECK_DEPTH,
+            random_margin: RANDOM_MARGIN,
+        }
+    }
+}
+
+pub struct Tunable {
+    // A number exposed as a UCI spin option so external tools can tune it
+    pub name: &'static str,
+    pub default: i32,
+    pub min: i32,
+    pub max: i32,
+    // Size of the changes made by SPSA
+    pub step: i32,
+}
+
+const fn tunable(name: &'static str, default: i32, min: i32, max: i32, step: i32) -> Tunable {
+    Tunable {
+        name,
+        default,
+        min,
+        max,
+        step,
+    }
+}
+
+// The evaluation names match the constants in tables.rs written in camel case
+pub const TUNABLES: [Tunable; 22] = [
+    tunable("QsearchCheckDepth", QSEARCH_CHECK_DEPTH, 0, 20, 1),
+    tunable("RandomMargin", RANDOM_MARGIN, 0, 20, 1),
+    tunable("PawnValue", PAWN_VALUE, 50, 200, 5),
+    tunable("KnightValue", KNIGHT_VALUE, 150, 500, 10),
+    tunable("BishopValue", BISHOP_VALUE, 150, 500, 10),
+    tunable("RookValue", ROOK_VALUE, 300, 800, 15),
+    tunable("QueenValue", QUEEN_VALUE, 600, 1400, 25),
+    tunable("CenterControl", CENTER_CONTROL, 0, 30, 1),
+    tunable("BishopMobility", BISHOP_MOBILITY, 0, 20, 1),
+    tunable("KnightMobility", KNIGHT_MOBILITY, 0, 20, 1),
+    tunable("RookMobility", ROOK_MOBILITY, 0, 20, 1),
+    tunable("QueenMobility", QUEEN_MOBILITY, 0, 10, 1),
+    // Unlike the Texel tuner, which leaves it out because it doesn't change the full window
+    // evaluation, SPSA plays games so it can weigh the time saved against the pruning errors
+    tunable("LazyMargin", LAZY_MARGIN, 0, 500, 10),
+    tunable("BishopPair", BISHOP_PAIR, 0, 100, 4),
+    tunable("RookOpenFile", ROOK_OPEN_FILE, 0, 60, 3),
+    tunable("RookHalfOpenFile", ROOK_HALF_OPEN_FILE, 0, 40, 2),
+    tunable("RookSeventhRank", ROOK_SEVENTH_RANK, 0, 60, 3),
+    tunable("KnightOutpost", KNIGHT_OUTPOST, 0, 60, 3),
+    tunable("BishopOutpost", BISHOP_OUTPOST, 0, 40, 2),
+    tunable("BadBishop", BAD_BISHOP, 0, 20, 1),
+    tunable("TrappedBishop", TRAPPED_BISHOP, 0, 250, 10),
+    tunable("TrappedRook", TRAPPED_ROOK, 0, 150, 5),
+];
+
+pub fn find_tunable(name: &str) -> Option<&'static Tunable> {
+    // UCI option names are case insensitive
+    TUNABLES.iter().find(|x| x.name.eq_ignore_ascii_case(name))
+}
+
+pub fn camel_case(name: &str) -> String {
+    // Converts the name of a constant (KNIGHT_VALUE) into the name of its option (KnightValue)
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+pub fn spsa_input() -> String {
+    // Lists the tunables in the OpenBench SPSA input format:
+    // name, int, default, min, max, step, learning rate
+    TUNABLES
+        .iter()
+        .map(|x| {
+            format!(
+                "{}, int, {}, {}, {}, {}, 0.002\n",
+                x.name, x.default, x.min, x.max, x.step
+            )
+        })
+        .collect()
+}
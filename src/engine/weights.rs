@@ -0,0 +1,171 @@
+use super::tables::*;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+// Generates the struct, its defaults and the accessors from a single list of fields
+// Every field is listed once together with the constant of tables.rs holding its default
+macro_rules! weights {
+    (
+        tables: { $($table:ident: $table_const:ident,)* }
+        scalars: { $($scalar:ident: $scalar_const:ident,)* }
+    ) => {
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Weights {
+            // All the numbers used by the handcrafted evaluation
+            // The defaults are the constants from tables.rs
+            $(pub $table: [i32; 64],)*
+            $(pub $scalar: i32,)*
+        }
+
+        impl Default for Weights {
+            fn default() -> Self {
+                Weights {
+                    $($table: $table_const,)*
+                    $($scalar: $scalar_const,)*
+                }
+            }
+        }
+
+        impl Weights {
+            pub fn tables(&self) -> Vec<(&'static str, &[i32; 64])> {
+                // Piece-square tables together with the names of their constants
+                vec![$((stringify!($table_const), &self.$table),)*]
+            }
+
+            pub fn scalars(&self) -> Vec<(&'static str, i32)> {
+                // Single weights together with the names of their constants
+                vec![$((stringify!($scalar_const), self.$scalar),)*]
+            }
+
+            pub fn tables_mut(&mut self) -> Vec<(&'static str, &mut [i32; 64])> {
+                vec![$((stringify!($table_const), &mut self.$table),)*]
+            }
+
+            pub fn scalars_mut(&mut self) -> Vec<(&'static str, &mut i32)> {
+                vec![$((stringify!($scalar_const), &mut self.$scalar),)*]
+            }
+
+            pub fn values_mut(&mut self) -> Vec<&mut i32> {
+                // Every weight in the same order as in tables() followed by scalars()
+                let mut values: Vec<&mut i32> = Vec::new();
+                $(values.extend(self.$table.iter_mut());)*
+                $(values.push(&mut self.$scalar);)*
+                values
+            }
+        }
+    };
+}
+
+weights! {
+    tables: {
+        pawn_table: PAWN_TABLE,
+        knight_table: KNIGHT_TABLE,
+        bishop_table: BISHOP_TABLE,
+        rook_table: ROOK_TABLE,
+        queen_table: QUEEN_TABLE,
+        king_table: KING_TABLE,
+        king_table_endgame: KING_TABLE_ENDGAME,
+    }
+    scalars: {
+        // Material values
+        pawn_value: PAWN_VALUE,
+        knight_value: KNIGHT_VALUE,
+        bishop_value: BISHOP_VALUE,
+        rook_value: ROOK_VALUE,
+        queen_value: QUEEN_VALUE,
+        // Bonus for a piece attacking the center
+        center_control: CENTER_CONTROL,
+        // Bonus per reachable square
+        bishop_mobility: BISHOP_MOBILITY,
+        knight_mobility: KNIGHT_MOBILITY,
+        rook_mobility: ROOK_MOBILITY,
+        queen_mobility: QUEEN_MOBILITY,
+        // The rest of the evaluation is skipped if the score is this far outside the window
+        lazy_margin: LAZY_MARGIN,
+        // Piece-specific positional terms
+        bishop_pair: BISHOP_PAIR,
+        rook_open_file: ROOK_OPEN_FILE,
+        rook_half_open_file: ROOK_HALF_OPEN_FILE,
+        rook_seventh_rank: ROOK_SEVENTH_RANK,
+        knight_outpost: KNIGHT_OUTPOST,
+        bishop_outpost: BISHOP_OUTPOST,
+        bad_bishop: BAD_BISHOP,
+        trapped_bishop: TRAPPED_BISHOP,
+        trapped_rook: TRAPPED_ROOK,
+    }
+}
+
+impl Weights {
+    pub fn to_source(&self) -> String {
+        // Writes the weights in the same format as tables.rs so the file can replace it
+        let mut source = String::new();
+        for (name, table) in self.tables() {
+            writeln!(source, "pub const {}: [i32; 64] = [", name).unwrap();
+            for row in table.chunks(8) {
+                let row: Vec<String> = row.iter().map(|x| x.to_string()).collect();
+                writeln!(source, "    {},", row.join(", ")).unwrap();
+            }
+            writeln!(source, "];\n").unwrap();
+        }
+        for (name, value) in self.scalars() {
+            writeln!(source, "pub const {}: i32 = {};", name, value).unwrap();
+        }
+        source
+    }
+
+    pub fn from_toml(text: &str) -> Result<Weights, String> {
+        // Reads weights from a TOML file, the keys are the lowercase names of the constants
+        // e.g. pawn_value = 100 or pawn_table = [0, 0, ...]
+        // Missing keys keep their default value
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        let mut weights = Weights::default();
+        for (key, value) in &table {
+            let name = key.to_uppercase();
+            let table = weights.tables_mut().into_iter().find(|(x, _)| *x == name);
+            if let Some((_, target)) = table {
+                let values = value
+                    .as_array()
+                    .filter(|x| x.len() == 64)
+                    .ok_or(format!("{} must be an array of 64 integers", key))?;
+                for (target, value) in target.iter_mut().zip(values) {
+                    *target = to_i32(value).ok_or(format!("{} must only hold integers", key))?;
+                }
+                continue;
+            }
+            let (_, target) = weights
+                .scalars_mut()
+                .into_iter()
+                .find(|(x, _)| *x == name)
+                .ok_or(format!("Unknown parameter {}", key))?;
+            *target = to_i32(value).ok_or(format!("{} must be an integer", key))?;
+        }
+        Ok(weights)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Weights, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Weights::from_toml(&text)
+    }
+
+    pub fn to_toml(&self) -> String {
+        // Writes the weights in the format read by from_toml
+        let mut text = String::new();
+        for (name, value) in self.scalars() {
+            writeln!(text, "{} = {}", name.to_lowercase(), value).unwrap();
+        }
+        for (name, table) in self.tables() {
+            writeln!(text, "\n{} = [", name.to_lowercase()).unwrap();
+            for row in table.chunks(8) {
+                let row: Vec<String> = row.iter().map(|x| x.to_string()).collect();
+                writeln!(text, "    {},", row.join(", ")).unwrap();
+            }
+            writeln!(text, "]").unwrap();
+        }
+        text
+    }
+}
+
+fn to_i32(value: &toml::Value) -> Option<i32> {
+    value.as_integer().and_then(|x| i32::try_from(x).ok())
+}
@@ -1,23 +1,66 @@
 use regex::Regex;
-use std::io::{self, Write};
+use std::fs;
+use std::io::Write;
 
-use super::engine::Game;
+use super::engine::{
+    bench_position, divide, find_tunable, is_insufficient_material, spsa_input, Game, BENCH_DEPTH,
+    BENCH_FENS, TUNABLES,
+};
+use chess::{BoardStatus, Color, File, Piece, Rank, Square};
+use std::time::Instant;
 
 // Handlers for command line commands
 
-pub fn id(console: &mut io::StdoutLock) {
+pub fn id(console: &mut impl Write) {
     writeln!(console, "id name Rusty Mind 1.0")
         .unwrap_or_else(|_| panic!("Error writing to the standard output"));
     writeln!(console, "id author Vilém Učík")
         .unwrap_or_else(|_| panic!("Error writing to the standard output"));
 }
 
-pub fn options(console: &mut io::StdoutLock) {
+pub fn options(console: &mut impl Write) {
     writeln!(console, "option name Depth type spin default 2 min 1")
         .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    writeln!(
+        console,
+        "option name SyzygyPath type string default <empty>"
+    )
+    .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    writeln!(console, "option name EvalFile type string default <empty>")
+        .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    writeln!(
+        console,
+        "option name EvalParams type string default <empty>"
+    )
+    .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    writeln!(console, "option name UCI_Chess960 type check default false")
+        .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    writeln!(console, "option name OwnBook type check default false")
+        .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    writeln!(console, "option name BookFile type string default <empty>")
+        .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    writeln!(
+        console,
+        "option name BookDepth type spin default 20 min 0 max 200"
+    )
+    .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    for tunable in TUNABLES.iter() {
+        writeln!(
+            console,
+            "option name {} type spin default {} min {} max {}",
+            tunable.name, tunable.default, tunable.min, tunable.max
+        )
+        .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+    }
 }
 
-pub fn set_up(game: &mut Game, input: &str, console: &mut io::StdoutLock) {
+pub fn spsa(console: &mut impl Write) {
+    // Prints the tunable options in the OpenBench SPSA input format
+    write!(console, "{}", spsa_input())
+        .unwrap_or_else(|_| panic!("Error writing to the standard output"));
+}
+
+pub fn set_up(game: &mut Game, input: &str, console: &mut impl Write) {
     let re = Regex::new(r"^position\s+(fen\s+([^ ]+ [^ ]+ [^ ]+ [^ ]+ [^ ]+ [^ ]+)|startpos)(?:\s+moves((\s+\S+)+))?$").unwrap();
     let fen: &str;
     let mut moves = String::new();
@@ -43,20 +86,406 @@ pub fn set_up(game: &mut Game, input: &str, console: &mut io::StdoutLock) {
         Vec::new()
     };
 
-    game.set_board(fen, move_list);
+    if let Err(error) = game.set_board(fen, move_list) {
+        writeln!(console, "Invalid position: {}", error).unwrap();
+    }
 }
 
-pub fn set_depth(game: &mut Game, input: &str, console: &mut io::StdoutLock) {
-    let words: Vec<&str> = input.split_whitespace().collect();
-    let value = words.last().unwrap().parse::<i32>().unwrap();
-    if words.len() > 5 || value < 1 {
-        writeln!(console, "Invalid value").unwrap();
-        return;
+pub fn set_option(game: &mut Game, input: &str, console: &mut impl Write) {
+    // Dispatches the setoption command to the handler of the given option
+    let re = Regex::new(r"^setoption name (\S+) value").unwrap();
+    let name = match re.captures(input) {
+        Some(captures) => captures.get(1).unwrap().as_str(),
+        None => {
+            writeln!(console, "Internal command error").unwrap();
+            return;
+        }
+    };
+    match name {
+        "Depth" => set_depth(game, input, console),
+        "SyzygyPath" => set_syzygy_path(game, input, console),
+        "EvalFile" => set_eval_file(game, input, console),
+        "EvalParams" => set_eval_params(game, input, console),
+        "BookFile" => set_book_file(game, input, console),
+        "OwnBook" => set_own_book(game, input, console),
+        "UCI_Chess960" => set_chess960(game, input, console),
+        "BookDepth" => set_book_depth(game, input, console),
+        _ if find_tunable(name).is_some() => set_tunable(game, name, input, console),
+        _ => writeln!(console, "Unknown option: {}", name).unwrap(),
     }
+}
+
+pub fn set_tunable(game: &mut Game, name: &str, input: &str, console: &mut impl Write) {
+    let tunable = find_tunable(name).unwrap();
+    let value = match input.split_whitespace().last().map(|x| x.parse::<i32>()) {
+        Some(Ok(value)) if (tunable.min..=tunable.max).contains(&value) => value,
+        _ => {
+            writeln!(console, "Invalid value").unwrap();
+            return;
+        }
+    };
+    game.set_tunable(name, value);
+}
+
+pub fn set_depth(game: &mut Game, input: &str, console: &mut impl Write) {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let value = match words.last().map(|x| x.parse::<i32>()) {
+        Some(Ok(value)) if words.len() == 5 && value >= 1 => value,
+        _ => {
+            writeln!(console, "Invalid value").unwrap();
+            return;
+        }
+    };
     game.set_depth(value);
 }
 
-pub fn search(game: &mut Game, console: &mut io::StdoutLock) -> () {
+pub fn set_syzygy_path(game: &mut Game, input: &str, console: &mut impl Write) {
+    let re = Regex::new(r"^setoption name SyzygyPath value(?:\s+(.*))?$").unwrap();
+    let path = match re.captures(input) {
+        Some(captures) => captures.get(1).map_or("", |x| x.as_str().trim()),
+        None => {
+            writeln!(console, "Internal command error").unwrap();
+            return;
+        }
+    };
+    match game.set_syzygy_path(path) {
+        Ok(count) => writeln!(console, "info string found {} tablebase files", count).unwrap(),
+        Err(error) => {
+            writeln!(console, "info string could not load tablebases: {}", error).unwrap()
+        }
+    }
+}
+
+pub fn set_eval_file(game: &mut Game, input: &str, console: &mut impl Write) {
+    let re = Regex::new(r"^setoption name EvalFile value(?:\s+(.*))?$").unwrap();
+    let path = match re.captures(input) {
+        Some(captures) => captures.get(1).map_or("", |x| x.as_str().trim()),
+        None => {
+            writeln!(console, "Internal command error").unwrap();
+            return;
+        }
+    };
+    match game.set_eval_file(path) {
+        Ok(()) if path.is_empty() || path == "<empty>" => {
+            writeln!(console, "info string using the classical evaluation").unwrap()
+        }
+        Ok(()) => writeln!(console, "info string using the network {}", path).unwrap(),
+        Err(error) => writeln!(
+            console,
+            "info string could not load the network, using the classical evaluation: {}",
+            error
+        )
+        .unwrap(),
+    }
+}
+
+pub fn set_eval_params(game: &mut Game, input: &str, console: &mut impl Write) {
+    let re = Regex::new(r"^setoption name EvalParams value(?:\s+(.*))?$").unwrap();
+    let path = match re.captures(input) {
+        Some(captures) => captures.get(1).map_or("", |x| x.as_str().trim()),
+        None => {
+            writeln!(console, "Internal command error").unwrap();
+            return;
+        }
+    };
+    match game.set_eval_params(path) {
+        Ok(()) if path.is_empty() || path == "<empty>" => writeln!(
+            console,
+            "info string using the default evaluation parameters"
+        )
+        .unwrap(),
+        Ok(()) => writeln!(
+            console,
+            "info string using the evaluation parameters {}",
+            path
+        )
+        .unwrap(),
+        Err(error) => writeln!(
+            console,
+            "info string could not load the evaluation parameters: {}",
+            error
+        )
+        .unwrap(),
+    }
+}
+
+pub fn set_book_file(game: &mut Game, input: &str, console: &mut impl Write) {
+    let re = Regex::new(r"^setoption name BookFile value(?:\s+(.*))?$").unwrap();
+    let path = match re.captures(input) {
+        Some(captures) => captures.get(1).map_or("", |x| x.as_str().trim()),
+        None => {
+            writeln!(console, "Internal command error").unwrap();
+            return;
+        }
+    };
+    match game.set_book_file(path) {
+        Ok(count) => writeln!(console, "info string found {} book entries", count).unwrap(),
+        Err(error) => writeln!(console, "info string could not load the book: {}", error).unwrap(),
+    }
+}
+
+pub fn set_own_book(game: &mut Game, input: &str, console: &mut impl Write) {
+    match input.split_whitespace().last() {
+        Some("true") => game.set_own_book(true),
+        Some("false") => game.set_own_book(false),
+        _ => writeln!(console, "Invalid value").unwrap(),
+    }
+}
+
+pub fn set_chess960(game: &mut Game, input: &str, console: &mut impl Write) {
+    match input.split_whitespace().last() {
+        Some("true") => game.set_chess960(true),
+        Some("false") => game.set_chess960(false),
+        _ => writeln!(console, "Invalid value").unwrap(),
+    }
+}
+
+pub fn set_book_depth(game: &mut Game, input: &str, console: &mut impl Write) {
+    match input.split_whitespace().last().map(|x| x.parse::<u32>()) {
+        Some(Ok(value)) => game.set_book_depth(value),
+        _ => writeln!(console, "Invalid value").unwrap(),
+    }
+}
+
+pub fn search(game: &mut Game, console: &mut impl Write) {
     let best_move = game.play();
     writeln!(console, "bestmove {}", best_move).unwrap();
 }
+
+pub fn run_perft(game: &mut Game, input: &str, console: &mut impl Write) {
+    // Handles "perft <depth>", "divide <depth>" and "go perft <depth>"
+    // Prints the number of leaf nodes below every root move and their sum
+    let depth = match input.split_whitespace().last().map(|x| x.parse::<u32>()) {
+        Some(Ok(depth)) => depth,
+        _ => {
+            writeln!(console, "Invalid value").unwrap();
+            return;
+        }
+    };
+    let start = Instant::now();
+    let counts = divide(&game.board, depth);
+    let nodes: u64 = if depth == 0 {
+        1
+    } else {
+        counts.iter().map(|(_, count)| count).sum()
+    };
+    if !input.starts_with("perft") {
+        for (mv, count) in &counts {
+            writeln!(console, "{}: {}", mv, count).unwrap();
+        }
+        writeln!(console).unwrap();
+    }
+    let elapsed = start.elapsed().as_millis().max(1);
+    writeln!(console, "Nodes searched: {}", nodes).unwrap();
+    writeln!(
+        console,
+        "info string time {} ms, {} nps",
+        elapsed,
+        nodes as u128 * 1000 / elapsed
+    )
+    .unwrap();
+}
+
+pub fn bench(input: &str, console: &mut impl Write) {
+    // Handles "bench [depth]": searches the built-in positions with a fresh hash
+    // The total node count is deterministic and works as a signature of the search
+    let depth = match input.split_whitespace().nth(1).map(|x| x.parse::<i32>()) {
+        None => BENCH_DEPTH,
+        Some(Ok(depth)) if depth > 0 => depth,
+        _ => {
+            writeln!(console, "Invalid value").unwrap();
+            return;
+        }
+    };
+    let start = Instant::now();
+    let mut nodes = 0;
+    for (index, fen) in BENCH_FENS.iter().enumerate() {
+        let count = bench_position(fen, depth);
+        writeln!(
+            console,
+            "Position {}/{}: {} nodes",
+            index + 1,
+            BENCH_FENS.len(),
+            count
+        )
+        .unwrap();
+        console.flush().unwrap();
+        nodes += count;
+    }
+    let elapsed = start.elapsed().as_millis().max(1);
+    writeln!(console).unwrap();
+    writeln!(console, "Total time (ms) : {}", elapsed).unwrap();
+    writeln!(console, "Nodes searched  : {}", nodes).unwrap();
+    writeln!(
+        console,
+        "Nodes/second    : {}",
+        nodes as u128 * 1000 / elapsed
+    )
+    .unwrap();
+}
+
+pub fn pgn(game: &Game, input: &str, console: &mut impl Write) {
+    // Handles "pgn [nocomments] [<file>]": writes the current game as PGN
+    // to the file or to the standard output
+    let mut words = input.split_whitespace().skip(1).peekable();
+    let comments = words.next_if_eq(&"nocomments").is_none();
+    let path: Vec<&str> = words.collect();
+    let pgn = game.to_pgn("?", "?", comments);
+    if path.is_empty() {
+        write!(console, "{}", pgn).unwrap();
+        return;
+    }
+    let path = path.join(" ");
+    match fs::write(&path, pgn) {
+        Ok(()) => writeln!(console, "info string game written to {}", path).unwrap(),
+        Err(error) => writeln!(console, "info string could not write {}: {}", path, error).unwrap(),
+    }
+}
+
+pub fn board(game: &Game, input: &str, flipped: bool, console: &mut impl Write) {
+    // Handles ".board [ascii]": draws the board with coordinates and the side to move
+    // Unicode figures are used unless ascii is given
+    let unicode = !input.split_whitespace().any(|x| x == "ascii");
+    let ranks: Vec<usize> = if flipped {
+        (0..8).collect()
+    } else {
+        (0..8).rev().collect()
+    };
+    let files: Vec<usize> = if flipped {
+        (0..8).rev().collect()
+    } else {
+        (0..8).collect()
+    };
+    let footer: String = files
+        .iter()
+        .map(|file| format!(" {}", (b'a' + *file as u8) as char))
+        .collect();
+    writeln!(console, "  +-----------------+").unwrap();
+    for rank in &ranks {
+        let mut line = format!("{} |", rank + 1);
+        for file in &files {
+            let square = Square::make_square(Rank::from_index(*rank), File::from_index(*file));
+            let symbol = match (game.board.piece_on(square), game.board.color_on(square)) {
+                (Some(piece), Some(color)) => piece_symbol(piece, color, unicode),
+                _ => '.',
+            };
+            line.push(' ');
+            line.push(symbol);
+        }
+        writeln!(console, "{} |", line).unwrap();
+    }
+    writeln!(console, "  +-----------------+").unwrap();
+    writeln!(console, "   {}", footer.trim_start()).unwrap();
+    let side = match game.board.side_to_move() {
+        Color::White => "White",
+        Color::Black => "Black",
+    };
+    writeln!(console, "{} to move", side).unwrap();
+}
+
+fn piece_symbol(piece: Piece, color: Color, unicode: bool) -> char {
+    let index = piece.to_index();
+    match (unicode, color) {
+        (true, Color::White) => ['♙', '♘', '♗', '♖', '♕', '♔'][index],
+        (true, Color::Black) => ['♟', '♞', '♝', '♜', '♛', '♚'][index],
+        (false, Color::White) => ['P', 'N', 'B', 'R', 'Q', 'K'][index],
+        (false, Color::Black) => ['p', 'n', 'b', 'r', 'q', 'k'][index],
+    }
+}
+
+fn game_over(game: &Game, console: &mut impl Write) -> bool {
+    // Prints the result if the game has ended
+    let reason = match game.board.status() {
+        BoardStatus::Checkmate => "checkmate",
+        BoardStatus::Stalemate => "stalemate",
+        BoardStatus::Ongoing if is_insufficient_material(&game.board) => "insufficient material",
+        BoardStatus::Ongoing => return false,
+    };
+    writeln!(console, "Game over: {} ({})", game.result(), reason).unwrap();
+    true
+}
+
+pub fn new_game(game: &mut Game, flipped: bool, console: &mut impl Write) {
+    // Handles ".new": starts a new game from the standard position
+    game.reset();
+    board(game, "", flipped, console);
+}
+
+pub fn engine_move(game: &mut Game, flipped: bool, console: &mut impl Write) {
+    // Handles ".go": the engine plays a move for the side to move
+    if game_over(game, console) {
+        return;
+    }
+    let chess_move = game.play_display();
+    let san = game.to_san(chess_move);
+    game.make_move(chess_move);
+    writeln!(console, "Engine plays {}", san).unwrap();
+    board(game, "", flipped, console);
+    game_over(game, console);
+}
+
+pub fn human_move(game: &mut Game, input: &str, flipped: bool, console: &mut impl Write) {
+    // Handles ".move <move>": plays the move in coordinate notation or SAN and lets the engine reply
+    if game_over(game, console) {
+        return;
+    }
+    let text = input.split_whitespace().nth(1).unwrap_or("");
+    let chess_move = match game.parse_move(text) {
+        Some(chess_move) => chess_move,
+        None => {
+            writeln!(console, "Illegal move: {}", text).unwrap();
+            return;
+        }
+    };
+    game.make_move(chess_move);
+    if game_over(game, console) {
+        board(game, "", flipped, console);
+        return;
+    }
+    engine_move(game, flipped, console);
+}
+
+pub fn undo(game: &mut Game, flipped: bool, console: &mut impl Write) {
+    // Handles ".undo": takes back the player's last move together with the engine's reply
+    // The engine's moves are the ones with search info, a move without a reply is taken back alone
+    let engine_move = match game.history().last() {
+        Some(record) => record.score.is_some(),
+        None => {
+            writeln!(console, "No moves to take back").unwrap();
+            return;
+        }
+    };
+    game.undo();
+    if engine_move {
+        game.undo();
+    }
+    board(game, "", flipped, console);
+}
+
+pub fn redo(game: &mut Game, flipped: bool, console: &mut impl Write) {
+    // Handles ".redo": replays the player's move and the engine's reply taken back by ".undo"
+    if game.redo().is_none() {
+        writeln!(console, "No moves to replay").unwrap();
+        return;
+    }
+    if game
+        .next_redo()
+        .is_some_and(|record| record.score.is_some())
+    {
+        game.redo();
+    }
+    board(game, "", flipped, console);
+}
+
+pub fn go_to(game: &mut Game, input: &str, flipped: bool, console: &mut impl Write) {
+    // Handles ".goto <n>": shows the position after the first n moves (plies) of the game
+    match input.split_whitespace().nth(1).map(|x| x.parse::<usize>()) {
+        Some(Ok(index)) if game.go_to(index) => board(game, "", flipped, console),
+        _ => writeln!(console, "Invalid value").unwrap(),
+    }
+}
+
+pub fn fen(game: &Game, console: &mut impl Write) {
+    // Handles ".fen": prints the FEN of the current position
+    writeln!(console, "{}", game.fen()).unwrap();
+}
@@ -1,5 +1,6 @@
 use regex::Regex;
 use rusty_mind as rm;
+use std::env;
 use std::io::{self, BufRead, Write};
 
 fn main() {
@@ -7,10 +8,48 @@ fn main() {
     let stdout = io::stdout();
     let mut handle = stdin.lock();
     let mut out = stdout.lock();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // "rusty_mind bench [depth]" runs the benchmark and exits
+    if args.first().map(|x| x.as_str()) == Some("bench") && args.len() <= 2 {
+        rm::bench(&args.join(" "), &mut out);
+        return;
+    }
+
     let mut game = rm::Game::new();
     writeln!(out, "Rusty Mind 0.1").unwrap();
     writeln!(out, "type .help to get the list of commands").unwrap();
 
+    // Evaluation parameters can also be given on the command line: rusty_mind --eval-params <file>
+    match args.as_slice() {
+        [] => (),
+        [flag, path] if flag == "--eval-params" => rm::set_option(
+            &mut game,
+            &format!("setoption name EvalParams value {}", path),
+            &mut out,
+        ),
+        _ => writeln!(
+            out,
+            "Usage: rusty_mind [--eval-params <file> | bench [depth]]"
+        )
+        .unwrap(),
+    }
+
+    let re1 = Regex::new(r"^setoption name (\S+) value").unwrap();
+    let re2 = Regex::new(
+        r"^position\s+(fen\s+([^ ]+ [^ ]+ [^ ]+ [^ ]+ [^ ]+ [^ ]+)|startpos)(?:\s+moves(\s+\S+)+)?",
+    )
+    .unwrap();
+    let re3 = Regex::new(r"^go(?:\s.*)?$").unwrap();
+    let re4 = Regex::new(r"^(?:perft|divide|go perft)\s+\d+$").unwrap();
+    let re5 = Regex::new(r"^bench(?:\s+\S+)?$").unwrap();
+    let re6 = Regex::new(r"^pgn(?:\s.*)?$").unwrap();
+    let re7 = Regex::new(r"^\.move\s+\S+$").unwrap();
+    let re8 = Regex::new(r"^\.board(?:\s+ascii)?$").unwrap();
+    let re9 = Regex::new(r"^\.goto\s+\S+$").unwrap();
+    // Orientation of the board in the human-play mode
+    let mut flipped = false;
+
     loop {
         let mut input = String::new();
         if handle.read_line(&mut input).is_err() {
@@ -37,6 +76,41 @@ fn main() {
                     "setoption name Depth value <depth> - set the search depth"
                 )
                 .unwrap();
+                writeln!(
+                    out,
+                    "setoption name SyzygyPath value <path> - set the Syzygy tablebase directories"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "setoption name EvalFile value <path> - set the NNUE network file"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "setoption name EvalParams value <path> - set the TOML file with the evaluation weights"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "setoption name BookFile value <path> - set the Polyglot opening book"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "setoption name OwnBook value <true|false> - play moves from the book"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "setoption name BookDepth value <plies> - use the book only in the first plies"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "setoption name UCI_Chess960 value <true|false> - play Chess960 and start a new game"
+                )
+                .unwrap();
                 writeln!(
                     out,
                     "position [fen <fenstring> | startpos] [moves <move1> <move2> ...]
@@ -45,8 +119,60 @@ fn main() {
                 .unwrap();
                 writeln!(out, "    fen - sets the baord from a FEN string").unwrap();
                 writeln!(out, "    startpos - sets up the default chess board").unwrap();
-                writeln!(out, "    moves - plays moves onto the board").unwrap();
+                writeln!(
+                    out,
+                    "    moves - plays moves (coordinate notation or SAN) onto the board"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "setoption name <tunable> value <n> - change a search or evaluation constant"
+                )
+                .unwrap();
+                writeln!(out, "spsa - list the tunable constants for SPSA tuning").unwrap();
                 writeln!(out, "go - start searching").unwrap();
+                writeln!(
+                    out,
+                    "perft <depth> - count the leaf nodes of the move tree of the given depth"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "divide <depth> | go perft <depth> - perft split by the root moves"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "bench [depth] - search the benchmark positions and print the node count"
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "pgn [nocomments] [<file>] - write the current game as PGN with the engine's scores"
+                )
+                .unwrap();
+                writeln!(out, "Playing against the engine:").unwrap();
+                writeln!(out, "    .new - start a new game").unwrap();
+                writeln!(
+                    out,
+                    "    .move <move> - play a move (e2e4 or SAN) and let the engine reply"
+                )
+                .unwrap();
+                writeln!(out, "    .go - let the engine play the side to move").unwrap();
+                writeln!(
+                    out,
+                    "    .undo - take back your last move and the engine's reply"
+                )
+                .unwrap();
+                writeln!(out, "    .redo - replay the moves taken back by .undo").unwrap();
+                writeln!(
+                    out,
+                    "    .goto <n> - go to the position after the first n plies of the game"
+                )
+                .unwrap();
+                writeln!(out, "    .fen - print the FEN of the current position").unwrap();
+                writeln!(out, "    .board [ascii] - show the board").unwrap();
+                writeln!(out, "    .flip - turn the board around").unwrap();
                 writeln!(out, "quit - exit the program").unwrap();
                 out.flush().unwrap();
             }
@@ -62,22 +188,63 @@ fn main() {
             }
 
             "ucinewgame" => {
-                game = rm::Game::new();
+                game.reset();
+                out.flush().unwrap();
+            }
+            "spsa" => {
+                rm::spsa(&mut out);
+                out.flush().unwrap();
+            }
+            ".new" => {
+                rm::new_game(&mut game, flipped, &mut out);
+                out.flush().unwrap();
+            }
+            ".go" => {
+                rm::engine_move(&mut game, flipped, &mut out);
+                out.flush().unwrap();
+            }
+            ".undo" => {
+                rm::undo(&mut game, flipped, &mut out);
+                out.flush().unwrap();
+            }
+            ".redo" => {
+                rm::redo(&mut game, flipped, &mut out);
+                out.flush().unwrap();
+            }
+            ".fen" => {
+                rm::fen(&game, &mut out);
+                out.flush().unwrap();
+            }
+            ".flip" => {
+                flipped = !flipped;
+                rm::board(&game, "", flipped, &mut out);
                 out.flush().unwrap();
             }
             "quit" => break,
             // handles the rest of the commands
             _ => {
-                let re1 = Regex::new(r"setoption name Depth value (\d+)").unwrap();
-                let re2 = Regex::new(
-            r"^position\s+(fen\s+([^ ]+ [^ ]+ [^ ]+ [^ ]+ [^ ]+ [^ ]+)|startpos)(?:\s+moves(\s+\S+)+)?"
-                ).unwrap();
-                let re3 = Regex::new(r"^go(?:\s.*)?$").unwrap();
-
                 if re1.is_match(command) {
-                    rm::set_depth(&mut game, command, &mut out);
+                    rm::set_option(&mut game, command, &mut out);
                 } else if re2.is_match(command) {
                     rm::set_up(&mut game, command, &mut out);
+                } else if re4.is_match(command) {
+                    rm::run_perft(&mut game, command, &mut out);
+                    out.flush().unwrap();
+                } else if re5.is_match(command) {
+                    rm::bench(command, &mut out);
+                    out.flush().unwrap();
+                } else if re6.is_match(command) {
+                    rm::pgn(&game, command, &mut out);
+                    out.flush().unwrap();
+                } else if re7.is_match(command) {
+                    rm::human_move(&mut game, command, flipped, &mut out);
+                    out.flush().unwrap();
+                } else if re9.is_match(command) {
+                    rm::go_to(&mut game, command, flipped, &mut out);
+                    out.flush().unwrap();
+                } else if re8.is_match(command) {
+                    rm::board(&game, command, flipped, &mut out);
+                    out.flush().unwrap();
                 } else if re3.is_match(command) {
                     // Implement the search directly without threading for now
                     rm::search(&mut game, &mut out);
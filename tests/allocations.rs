@@ -0,0 +1,42 @@
+use chess::Board;
+use rusty_mind as rm;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Counts the allocations of this test binary
+struct Counter;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static COUNTER: Counter = Counter;
+
+#[test]
+fn search_does_not_allocate() {
+    // The first search grows the evaluator's stack, the second one must not allocate at all
+    let positions = [
+        rm::STARTING_FEN,
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+    let mut engine = rm::Engine::with_cache_size(1 << 12);
+    for fen in positions {
+        let board = Board::from_str(fen).unwrap();
+        engine.search(&board, 3);
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        engine.search(&board, 3);
+        assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), before, "{}", fen);
+    }
+}
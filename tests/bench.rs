@@ -0,0 +1,38 @@
+use chess::Board;
+use rusty_mind as rm;
+use std::str::FromStr;
+
+// A few of the bench positions which stay quick in debug builds
+const POSITIONS: std::ops::Range<usize> = 5..10;
+const DEPTH: i32 = 1;
+
+fn bench() -> u64 {
+    rm::BENCH_FENS[POSITIONS]
+        .iter()
+        .map(|fen| rm::bench_position(fen, DEPTH))
+        .sum()
+}
+
+#[test]
+fn node_count_is_deterministic() {
+    let nodes = bench();
+    assert!(nodes > 0);
+    assert_eq!(bench(), nodes);
+}
+
+#[test]
+fn node_count_ignores_the_random_margin() {
+    // The cushion only changes which of the root moves is picked, never which nodes are searched
+    let nodes = bench();
+    for (margin, seed) in [(0, 1), (20, 2), (20, 3)] {
+        let mut total = 0;
+        for fen in &rm::BENCH_FENS[POSITIONS] {
+            let mut engine = rm::Engine::with_cache_size(rm::BENCH_CACHE_SIZE);
+            assert!(engine.set_tunable("RandomMargin", margin));
+            engine.set_seed(seed);
+            engine.search(&Board::from_str(fen).unwrap(), DEPTH);
+            total += engine.nodes();
+        }
+        assert_eq!(total, nodes, "margin {}, seed {}", margin, seed);
+    }
+}
@@ -0,0 +1,80 @@
+use chess::{Board, ChessMove};
+use rusty_mind as rm;
+use std::env;
+use std::fs;
+use std::str::FromStr;
+
+fn game(moves: &str) -> Vec<(Board, ChessMove)> {
+    // The positions of a game together with the moves played in them
+    let mut board = Board::default();
+    let mut positions = Vec::new();
+    for text in moves.split_whitespace() {
+        let mv = ChessMove::from_str(text).unwrap();
+        positions.push((board, mv));
+        board = board.make_move_new(mv);
+    }
+    positions
+}
+
+fn after(moves: &str) -> Board {
+    game(moves)
+        .last()
+        .map(|(board, mv)| board.make_move_new(*mv))
+        .unwrap()
+}
+
+fn moves(book: &rm::Book, board: &Board) -> Vec<(String, u16)> {
+    book.moves(board)
+        .into_iter()
+        .map(|(mv, weight)| (mv.to_string(), weight))
+        .collect()
+}
+
+#[test]
+fn build_open_and_pick() {
+    let mut builder = rm::BookBuilder::new();
+    builder.win_weight = 3;
+    builder.draw_weight = 2;
+    builder.loss_weight = 0;
+    builder.add_game(&game("e2e4 e7e5"), "1/2-1/2");
+    builder.add_game(&game("e2e4 c7c5"), "0-1");
+    builder.add_game(&game("d2d4 d7d5"), "1-0");
+    builder.add_game(&game("c2c4"), "*");
+
+    let path = env::temp_dir().join(format!("rusty_mind_book_{}.bin", std::process::id()));
+    assert_eq!(builder.write(&path).unwrap(), 4);
+    let book = rm::Book::open(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(book.len(), 4);
+
+    // Both sides get the draw weight for a drawn game, the lost d5 isn't written at all
+    let start = Board::default();
+    assert_eq!(
+        moves(&book, &start),
+        [("d2d4".to_string(), 3), ("e2e4".to_string(), 2)]
+    );
+    assert_eq!(
+        moves(&book, &after("e2e4")),
+        [("c7c5".to_string(), 3), ("e7e5".to_string(), 2)]
+    );
+    assert!(moves(&book, &after("d2d4")).is_empty());
+    assert_eq!(book.pick(&after("d2d4")), None);
+    for _ in 0..20 {
+        let mv = book.pick(&start).unwrap().to_string();
+        assert!(mv == "d2d4" || mv == "e2e4", "{}", mv);
+    }
+}
+
+#[test]
+fn draws_only_weights() {
+    // Weights which don't add up like Polyglot's must not underflow
+    let mut builder = rm::BookBuilder::new();
+    builder.win_weight = 0;
+    builder.draw_weight = 1;
+    builder.loss_weight = 0;
+    builder.add_game(&game("e2e4 e7e5"), "1/2-1/2");
+    builder.add_game(&game("e2e4 c7c5"), "1-0");
+    let entries = builder.entries();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|entry| entry.weight == 1));
+}
@@ -0,0 +1,135 @@
+use chess::{ChessMove, MoveGen};
+use rusty_mind as rm;
+
+const START: &str = "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1";
+
+fn game(fen: &str, moves: &[&str]) -> rm::Game {
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_chess960(true);
+    game.set_board(fen, moves.to_vec()).unwrap();
+    game
+}
+
+#[test]
+fn castling_moves() {
+    // Castling is written as the king taking its own rook, SAN and the FEN use the usual notation
+    let game = game(START, &["e1b1", "e8g8"]);
+    let sans: Vec<&str> = game.history().iter().map(|x| x.san.as_str()).collect();
+    assert_eq!(sans, ["O-O-O", "O-O"]);
+    assert_eq!(
+        game.fen(),
+        "1r3rk1/pppppppp/8/8/8/8/PPPPPPPP/2KR2R1 w - - 2 2"
+    );
+}
+
+#[test]
+fn undo_restores_castling_rights() {
+    let mut game = game(START, &["O-O-O"]);
+    game.undo();
+    assert_eq!(
+        game.fen(),
+        "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1"
+    );
+    assert!(game.parse_move("e1g1").is_some());
+    game.redo();
+    assert_eq!(game.history()[0].chess_move.to_string(), "e1b1");
+    assert!(game.parse_move("e8g8").is_some());
+}
+
+#[test]
+fn standard_start_in_chess960_mode() {
+    // The king takes the rook in the standard position too
+    let game = game(
+        rm::STARTING_FEN,
+        &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1h1"],
+    );
+    assert_eq!(game.history()[6].san, "O-O");
+    assert_eq!(
+        game.fen(),
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4"
+    );
+}
+
+#[test]
+fn search_castles_inside_the_tree() {
+    // Both black moves run into O-O#, the king on g1 keeps the rook from reaching f1 otherwise
+    let position = rm::Chess960::from_fen("5k2/p1N1p1pB/4P1P1/8/8/8/8/6KR b K - 0 1").unwrap();
+    let board = position.uncastled().board;
+    let mut engine = rm::Engine::with_cache_size(1 << 12);
+    engine.set_castling(position.castling_rooks());
+    let (_, score) = engine.search(&board, 2).unwrap();
+    assert!(score <= -90_000, "{}", score);
+    // Without the castling rights there is no mate
+    engine.set_castling(rm::CastlingRooks::default());
+    let (_, score) = engine.search(&board, 2).unwrap();
+    assert!(score > -90_000, "{}", score);
+}
+
+#[test]
+fn quiescence_castles_out_of_a_stalemate() {
+    // Castling is white's only legal move, the chess crate sees a stalemate
+    let position = rm::Chess960::from_fen("3k1r2/8/8/8/8/7p/7P/6KR w K - 0 1").unwrap();
+    let board = position.uncastled().board;
+    let mut engine = rm::Engine::with_cache_size(1 << 10);
+    engine.set_castling(position.castling_rooks());
+    let score = engine.quiescence(&board);
+    // The score is the one of the position after castling
+    let mut castled = position.clone();
+    assert!(castled.make_move(castled.parse_move("O-O").unwrap()));
+    let mut engine = rm::Engine::with_cache_size(1 << 10);
+    engine.set_castling(castled.castling_rooks());
+    let expected = -engine.quiescence(&castled.uncastled().board);
+    assert_ne!(expected, 0);
+    assert_eq!(score, expected);
+}
+
+#[test]
+fn castling_rooks_follow_the_game() {
+    // The rights kept by the search give the same castling moves and boards as the full rules
+    let mut rng: u64 = 1;
+    for fen in [
+        START,
+        "rk2r3/pppppppp/8/8/8/8/PPPPPPPP/RK2R3 w KQkq - 0 1",
+        "2r1kr2/pppppppp/8/8/8/8/PPPPPPPP/2R1KR2 w KQkq - 0 1",
+        "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+    ] {
+        for _ in 0..20 {
+            let mut position = rm::Chess960::from_fen(fen).unwrap();
+            let mut rooks = position.castling_rooks();
+            for _ in 0..40 {
+                let board = position.uncastled().board;
+                let castles = rooks.castles(&board);
+                let mut moves: Vec<ChessMove> = castles.iter().map(|(mv, _)| *mv).collect();
+                let mut expected = position.castling_moves();
+                moves.sort_by_key(|mv| mv.to_string());
+                expected.sort_by_key(|mv| mv.to_string());
+                assert_eq!(moves, expected, "{}", position.fen());
+                for (mv, after) in &castles {
+                    let mut next = position.clone();
+                    assert!(next.make_move(*mv));
+                    assert_eq!(*after, next.uncastled().board);
+                    assert_eq!(rooks.after(&board, *mv), next.castling_rooks());
+                }
+
+                // A random move, castling included
+                let mut legal: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                legal.extend(moves);
+                if legal.is_empty() {
+                    break;
+                }
+                rng = rng.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                let mv = legal[(rng >> 33) as usize % legal.len()];
+                rooks = rooks.after(&board, mv);
+                assert!(position.make_move(mv));
+                assert_eq!(rooks, position.castling_rooks(), "{}", position.fen());
+            }
+        }
+    }
+}
+
+#[test]
+fn game_plays_castling_moves() {
+    let mut game = game("5k2/2N1p1pB/p3P1P1/8/8/8/8/6KR w K - 0 2", &[]);
+    game.set_depth(1);
+    assert_eq!(game.play().to_string(), "g1h1");
+}
@@ -0,0 +1,94 @@
+use chess::Board;
+use rusty_mind as rm;
+use std::str::FromStr;
+
+fn board(fen: &str) -> Board {
+    Board::from_str(fen).unwrap()
+}
+
+fn endgame(fen: &str) -> Option<i32> {
+    let board = board(fen);
+    rm::endgame_evaluation(&board, &rm::MaterialCount::new(&board))
+}
+
+#[test]
+fn kbnk_drives_to_the_bishop_corner() {
+    // The light squared bishop mates in a8 or h1, both kings are as far from the white king
+    let right_corner = endgame("8/1B6/5N2/3K4/8/8/8/7k w - - 0 1").unwrap();
+    let wrong_corner = endgame("8/1B6/5N2/3K4/8/8/8/k7 w - - 0 1").unwrap();
+    assert!(right_corner > wrong_corner);
+    assert!(wrong_corner > 0);
+    // Relative to the side to move
+    assert_eq!(
+        endgame("8/1B6/5N2/3K4/8/8/8/7k b - - 0 1"),
+        Some(-right_corner)
+    );
+}
+
+#[test]
+fn bare_king_driven_to_the_edge() {
+    let edge = endgame("7K/8/8/8/k7/8/8/7R w - - 0 1").unwrap();
+    let center = endgame("7K/8/8/8/3k4/8/8/7R w - - 0 1").unwrap();
+    assert!(edge > center);
+    // The queen counts for more than the rook
+    let queen = endgame("7K/8/8/8/k7/8/8/7Q w - - 0 1").unwrap();
+    assert!(queen > edge);
+    // Black can be the side with the material too
+    assert_eq!(endgame("7k/8/8/8/K7/8/8/7r b - - 0 1"), Some(edge));
+}
+
+#[test]
+fn insufficient_material() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1",
+        // Bishops on the same square color
+        "2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1",
+    ] {
+        assert!(rm::is_insufficient_material(&board(fen)), "{}", fen);
+        assert_eq!(endgame(fen), Some(0), "{}", fen);
+    }
+    for fen in [
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+        // Bishops on opposite square colors
+        "3bk3/8/8/8/8/8/8/3BK3 w - - 0 1",
+    ] {
+        assert!(!rm::is_insufficient_material(&board(fen)), "{}", fen);
+    }
+}
+
+#[test]
+fn wrong_rook_pawn() {
+    // The dark squared bishop doesn't control a8, the black king holds the corner
+    assert_eq!(endgame("k7/8/8/8/P7/8/8/2B1K3 w - - 0 1"), Some(0));
+    assert_eq!(endgame("1k6/8/8/8/P7/8/8/2B1K3 b - - 0 1"), Some(0));
+    // The light squared bishop wins
+    assert_eq!(endgame("k7/8/8/8/P7/8/8/3BK3 w - - 0 1"), None);
+    // The black king is too far from the corner
+    assert_eq!(endgame("8/8/8/5k2/P7/8/8/2B1K3 w - - 0 1"), None);
+}
+
+#[test]
+fn opposite_bishops_halve_the_evaluation() {
+    // Without the king tables and with the lazy evaluation skipped only the material is left
+    let weights = rm::Weights {
+        king_table: [0; 64],
+        king_table_endgame: [0; 64],
+        ..Default::default()
+    };
+    let unscaled = |board: &Board| rm::material(board, &weights);
+    let evaluation = |board: &Board| rm::evaluation(board, -999_999, -999_998, &weights);
+
+    let opposite = board("2b1k3/pp6/8/8/3PP3/8/PPP5/2B1K3 w - - 0 1");
+    assert_ne!(unscaled(&opposite), 0);
+    assert_eq!(evaluation(&opposite), unscaled(&opposite) * 32 / 64);
+    // Bishops on the same square color or other pieces keep the full evaluation
+    let same = board("3bk3/pp6/8/8/3PP3/8/PPP5/2B1K3 w - - 0 1");
+    assert_eq!(evaluation(&same), unscaled(&same));
+    let knights = board("2bnk3/pp6/8/8/3PP3/8/PPP5/2BNK3 w - - 0 1");
+    assert_eq!(evaluation(&knights), unscaled(&knights));
+}
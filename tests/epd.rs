@@ -0,0 +1,64 @@
+use chess::{Board, ChessMove};
+use rusty_mind as rm;
+use std::str::FromStr;
+
+fn operation(name: &str, operands: &[&str]) -> (String, Vec<String>) {
+    (
+        name.to_string(),
+        operands.iter().map(|x| x.to_string()).collect(),
+    )
+}
+
+#[test]
+fn operations() {
+    assert_eq!(
+        rm::parse_operations("bm Qd1+ Nf3; id \"WAC.001\";"),
+        vec![
+            operation("bm", &["Qd1+", "Nf3"]),
+            operation("id", &["WAC.001"])
+        ]
+    );
+    // Quoted operands keep their spaces and semicolons
+    assert_eq!(
+        rm::parse_operations("c0 \"a; b  c\"; id \"x\""),
+        vec![operation("c0", &["a; b  c"]), operation("id", &["x"])]
+    );
+    // Empty operations are skipped and the last semicolon is optional
+    assert_eq!(
+        rm::parse_operations(" ;; noop; dm 3"),
+        vec![operation("noop", &[]), operation("dm", &["3"])]
+    );
+    assert!(rm::parse_operations("").is_empty());
+}
+
+#[test]
+fn records() {
+    let record = rm::parse_epd(
+        "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";",
+    )
+    .unwrap();
+    assert_eq!(
+        record.board,
+        Board::from_str("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1").unwrap()
+    );
+    assert_eq!(record.id(), Some("WAC.001"));
+    assert_eq!(
+        record.moves("bm"),
+        vec![ChessMove::from_str("g3g6").unwrap()]
+    );
+    assert!(record.moves("am").is_empty());
+}
+
+#[test]
+fn optional_counters() {
+    let without = rm::parse_epd("4k3/8/8/8/8/8/8/4K2R w K - bm O-O; id \"a\";").unwrap();
+    let with = rm::parse_epd("4k3/8/8/8/8/8/8/4K2R w K - 0 1 bm O-O; id \"a\";").unwrap();
+    assert_eq!(with.board, without.board);
+    assert_eq!(with.operations, without.operations);
+    assert_eq!(with.moves("bm"), vec![ChessMove::from_str("e1g1").unwrap()]);
+    // A single number is an operand, not a counter
+    let record = rm::parse_epd("4k3/8/8/8/8/8/8/4K2R w K - 5 bm O-O;").unwrap();
+    assert_eq!(record.operations, vec![operation("5", &["bm", "O-O"])]);
+    assert!(rm::parse_epd("4k3/8/8/8/8/8/8/4K2R w K").is_none());
+    assert!(rm::parse_epd("4k3/8/8/8/8/8/8/4K2X w K - bm O-O;").is_none());
+}
@@ -0,0 +1,489 @@
+use chess::{Board, ChessMove, Color};
+use rm::Evaluator;
+use rusty_mind as rm;
+use std::str::FromStr;
+
+// Games with castling on both sides, en passant, promotions and captures of promoted pieces
+const GAMES: [(&str, &str); 3] = [
+    (
+        rm::STARTING_FEN,
+        "e2e4 c7c5 g1f3 b8c6 f1b5 g8f6 e1g1 d7d6 d2d4 c5d4 f3d4 c8d7 b1c3 e7e5 d4f5 f8e7",
+    ),
+    (
+        "r3k2r/pppq1ppp/2n2n2/3pp3/4P3/2N2N2/PPPQ1PPP/R3K2R b KQkq - 0 1",
+        "e8c8 e4d5 e5e4 d5c6 e4f3 c6b7 c8b8 e1c1 f3g2 h1g1",
+    ),
+    (
+        "8/1P4k1/8/3pP3/8/8/6Kp/8 w - d6 0 1",
+        "e5d6 h2h1q g2h1 g7f7 b7b8n f7e6 b8c6 e6d6",
+    ),
+];
+
+#[test]
+fn incremental_material_matches_full_recompute() {
+    for (fen, moves) in GAMES {
+        let weights = rm::Weights::default();
+        let mut evaluator = rm::Classical::new(weights.clone());
+        let mut board = Board::from_str(fen).unwrap();
+        evaluator.reset(&board);
+        let mut boards = vec![board];
+        for text in moves.split_whitespace() {
+            let mv = ChessMove::from_str(text).unwrap();
+            assert!(board.legal(mv), "{} in {}", text, board);
+            let next = board.make_move_new(mv);
+            evaluator.make_move(&board, &next);
+            board = next;
+            boards.push(board);
+            assert_eq!(
+                evaluator.evaluate(&board, -999_999, 999_999),
+                rm::evaluation(&board, -999_999, 999_999, &weights),
+                "{}",
+                board
+            );
+        }
+        // Taking the moves back returns to the stored values
+        for board in boards.iter().rev().skip(1) {
+            evaluator.unmake_move();
+            assert_eq!(
+                evaluator.evaluate(board, -999_999, 999_999),
+                rm::evaluation(board, -999_999, 999_999, &weights)
+            );
+        }
+    }
+}
+
+fn write_network(hidden: usize) -> std::path::PathBuf {
+    // Network with pseudo random weights over the whole i16 range, so the accumulators overflow
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut bytes = b"RMNN".to_vec();
+    bytes.extend((hidden as u32).to_le_bytes());
+    for _ in 0..(768 * hidden + hidden + 2 * hidden) {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+        bytes.extend(((seed >> 48) as i16).to_le_bytes());
+    }
+    bytes.extend(12_345i32.to_le_bytes());
+    let path = std::env::temp_dir().join(format!("rusty_mind_{}.nnue", std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[test]
+fn incremental_nnue_matches_full_refresh() {
+    let path = write_network(16);
+    let mut evaluator = rm::Nnue::load(&path).unwrap();
+    let mut refreshed = rm::Nnue::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    for (fen, moves) in GAMES {
+        let mut board = Board::from_str(fen).unwrap();
+        evaluator.reset(&board);
+        let mut boards = vec![board];
+        for text in moves.split_whitespace() {
+            let next = board.make_move_new(ChessMove::from_str(text).unwrap());
+            evaluator.make_move(&board, &next);
+            board = next;
+            boards.push(board);
+            refreshed.reset(&board);
+            assert_eq!(
+                evaluator.evaluate(&board, -999_999, 999_999),
+                refreshed.evaluate(&board, -999_999, 999_999),
+                "{}",
+                board
+            );
+        }
+        for board in boards.iter().rev().skip(1) {
+            evaluator.unmake_move();
+            refreshed.reset(board);
+            assert_eq!(
+                evaluator.evaluate(board, -999_999, 999_999),
+                refreshed.evaluate(board, -999_999, 999_999)
+            );
+        }
+    }
+}
+
+#[test]
+fn search_keeps_material_in_sync() {
+    // The evaluator asserts in debug builds that its material matches a full recompute
+    let mut engine = rm::Engine::with_cache_size(1 << 12);
+    for (fen, _) in GAMES {
+        let board = Board::from_str(fen).unwrap();
+        assert!(engine.search(&board, 3).is_some());
+    }
+}
+
+fn term(term: fn(&Board, Color, &rm::Weights) -> i32, fen: &str, color: Color) -> i32 {
+    term(
+        &Board::from_str(fen).unwrap(),
+        color,
+        &rm::Weights::default(),
+    )
+}
+
+#[test]
+fn bishop_pair() {
+    let weights = rm::Weights::default();
+    assert_eq!(
+        term(
+            rm::bishop_pair,
+            "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+            Color::White
+        ),
+        weights.bishop_pair
+    );
+    // Two bishops on the same square color don't make a pair
+    assert_eq!(
+        term(
+            rm::bishop_pair,
+            "4k3/8/8/8/8/8/1B6/2B1K3 w - - 0 1",
+            Color::White
+        ),
+        0
+    );
+    assert_eq!(
+        term(
+            rm::bishop_pair,
+            "2b1kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            Color::White
+        ),
+        0
+    );
+}
+
+#[test]
+fn rook_files() {
+    let weights = rm::Weights::default();
+    let open = "4k3/pppppp1p/8/8/8/8/PPPPPP1P/4K1R1 w - - 0 1";
+    let half_open = "4k3/pppppppp/8/8/8/8/PPPPPP1P/4K1R1 w - - 0 1";
+    let closed = "4k3/pppppppp/8/8/8/8/PPPPPPPP/4K1R1 w - - 0 1";
+    assert_eq!(
+        term(rm::rook_files, open, Color::White),
+        weights.rook_open_file
+    );
+    assert_eq!(
+        term(rm::rook_files, half_open, Color::White),
+        weights.rook_half_open_file
+    );
+    assert_eq!(term(rm::rook_files, closed, Color::White), 0);
+}
+
+#[test]
+fn rook_seventh_rank() {
+    let weights = rm::Weights::default();
+    // Counts only with the enemy king on the 8th rank or enemy pawns on the 7th
+    assert_eq!(
+        term(
+            rm::rook_seventh_rank,
+            "4k3/R7/8/8/8/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        weights.rook_seventh_rank
+    );
+    assert_eq!(
+        term(
+            rm::rook_seventh_rank,
+            "8/R6p/4k3/8/8/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        weights.rook_seventh_rank
+    );
+    assert_eq!(
+        term(
+            rm::rook_seventh_rank,
+            "8/R7/4k3/8/8/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        0
+    );
+    assert_eq!(
+        term(
+            rm::rook_seventh_rank,
+            "4k3/8/8/8/8/4K3/r7/8 b - - 0 1",
+            Color::Black
+        ),
+        0
+    );
+    assert_eq!(
+        term(
+            rm::rook_seventh_rank,
+            "4k3/8/8/8/8/8/r7/7K b - - 0 1",
+            Color::Black
+        ),
+        weights.rook_seventh_rank
+    );
+}
+
+#[test]
+fn outposts() {
+    let weights = rm::Weights::default();
+    assert_eq!(
+        term(
+            rm::outposts,
+            "4k3/8/8/3N4/4P3/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        weights.knight_outpost
+    );
+    assert_eq!(
+        term(
+            rm::outposts,
+            "4k3/8/8/3B4/2P5/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        weights.bishop_outpost
+    );
+    // An enemy pawn on an adjacent file can still chase the knight away
+    assert_eq!(
+        term(
+            rm::outposts,
+            "4k3/2p5/8/3N4/4P3/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        0
+    );
+    // Not protected by a pawn
+    assert_eq!(
+        term(
+            rm::outposts,
+            "4k3/8/8/3N4/8/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        0
+    );
+    // Black's outposts are on the 5th to 3rd rank
+    assert_eq!(
+        term(
+            rm::outposts,
+            "4k3/8/4p3/8/3n4/8/8/4K3 b - - 0 1",
+            Color::Black
+        ),
+        0
+    );
+    assert_eq!(
+        term(
+            rm::outposts,
+            "4k3/8/8/4p3/3n4/8/8/4K3 b - - 0 1",
+            Color::Black
+        ),
+        weights.knight_outpost
+    );
+}
+
+#[test]
+fn bad_bishop() {
+    let weights = rm::Weights::default();
+    // The c1 bishop and the pawns on b2 and d2 stand on dark squares, the pawn on e2 doesn't
+    assert_eq!(
+        term(
+            rm::bad_bishop,
+            "4k3/8/8/8/8/8/1P1PP3/2B1K3 w - - 0 1",
+            Color::White
+        ),
+        2 * weights.bad_bishop
+    );
+    assert_eq!(
+        term(
+            rm::bad_bishop,
+            "4k3/8/8/8/8/8/1P1PP3/4KB2 w - - 0 1",
+            Color::White
+        ),
+        weights.bad_bishop
+    );
+}
+
+#[test]
+fn trapped_pieces() {
+    let weights = rm::Weights::default();
+    assert_eq!(
+        term(
+            rm::trapped_pieces,
+            "4k3/B7/1p6/8/8/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        weights.trapped_bishop
+    );
+    assert_eq!(
+        term(
+            rm::trapped_pieces,
+            "4k3/8/8/8/8/1P6/b7/4K3 b - - 0 1",
+            Color::Black
+        ),
+        weights.trapped_bishop
+    );
+    assert_eq!(
+        term(
+            rm::trapped_pieces,
+            "4k3/8/B7/1p6/8/8/8/4K3 w - - 0 1",
+            Color::White
+        ),
+        weights.trapped_bishop / 2
+    );
+    // A rook shut in the corner by its uncastled king
+    assert_eq!(
+        term(
+            rm::trapped_pieces,
+            "4k3/8/8/8/8/8/8/5K1R w - - 0 1",
+            Color::White
+        ),
+        weights.trapped_rook
+    );
+    assert_eq!(
+        term(
+            rm::trapped_pieces,
+            "4k3/8/8/8/8/8/8/4K2R w K - 0 1",
+            Color::White
+        ),
+        0
+    );
+}
+
+#[test]
+fn switching_the_evaluator_clears_the_hash() {
+    let mut engine = rm::Engine::with_cache_size(1 << 12);
+    engine.search(&Board::default(), 2);
+    // The positions below the root are stored
+    let board = Board::default().make_move_new(ChessMove::from_str("e2e4").unwrap());
+    assert!(engine.probe_hash(&board).is_some());
+    engine.set_eval_file("").unwrap();
+    assert_eq!(engine.probe_hash(&board), None);
+}
+
+fn mirror(fen: &str) -> String {
+    // Swaps the colors and flips the board vertically, the position stays the same for the side to move
+    let swap_case = |text: &str| -> String {
+        text.chars()
+            .map(|x| {
+                if x.is_ascii_uppercase() {
+                    x.to_ascii_lowercase()
+                } else {
+                    x.to_ascii_uppercase()
+                }
+            })
+            .collect()
+    };
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let ranks: Vec<&str> = fields[0].split('/').rev().collect();
+    let side = if fields[1] == "w" { "b" } else { "w" };
+    let en_passant = match fields[3] {
+        "-" => "-".to_string(),
+        square => {
+            let rank = if &square[1..] == "3" { "6" } else { "3" };
+            format!("{}{}", &square[..1], rank)
+        }
+    };
+    format!(
+        "{} {} {} {} {}",
+        swap_case(&ranks.join("/")),
+        side,
+        swap_case(fields[2]),
+        en_passant,
+        fields[4..].join(" ")
+    )
+}
+
+#[test]
+fn tuned_weights_keep_the_colors_symmetric() {
+    // Texel tuning moves every entry of the tables on its own, every entry has to apply to both colors
+    let mut weights = rm::Weights::default();
+    let mut seed: u64 = 1;
+    for value in weights.values_mut() {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+        *value += (seed >> 33) as i32 % 21 - 10;
+    }
+    let white = |board: &Board| {
+        let eval = rm::evaluation(board, -999_999, 999_999, &weights);
+        if board.side_to_move() == Color::White {
+            eval
+        } else {
+            -eval
+        }
+    };
+    for fen in rm::BENCH_FENS {
+        let board = Board::from_str(fen).unwrap();
+        let mirrored = Board::from_str(&mirror(fen)).unwrap();
+        assert_eq!(white(&mirrored), -white(&board), "{}", fen);
+    }
+}
+
+fn placements(base: &str, piece: char) -> Vec<String> {
+    // The base position with one more piece on each empty square, kings are moved instead
+    let mut squares: Vec<char> = Vec::new();
+    for rank in base.split('/') {
+        for x in rank.chars() {
+            match x.to_digit(10) {
+                Some(empty) => squares.extend(std::iter::repeat_n('.', empty as usize)),
+                None => squares.push(x),
+            }
+        }
+    }
+    if piece == 'K' {
+        let king = squares.iter().position(|&x| x == 'K').unwrap();
+        squares[king] = '.';
+    }
+    let mut fens = Vec::new();
+    for square in 0..64 {
+        let back_rank = !(8..56).contains(&square);
+        if squares[square] != '.' || (piece == 'P' && back_rank) {
+            continue;
+        }
+        let mut placed = squares.clone();
+        placed[square] = piece;
+        let ranks: Vec<String> = placed
+            .chunks(8)
+            .map(|rank| {
+                let mut text = String::new();
+                let mut empty = 0;
+                for &x in rank {
+                    if x == '.' {
+                        empty += 1;
+                        continue;
+                    }
+                    if empty > 0 {
+                        text.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    text.push(x);
+                }
+                if empty > 0 {
+                    text.push_str(&empty.to_string());
+                }
+                text
+            })
+            .collect();
+        fens.push(format!("{} w - - 0 1", ranks.join("/")));
+    }
+    fens
+}
+
+#[test]
+fn every_piece_is_colors_symmetric() {
+    // Each piece type on every square, in an endgame and in a full middlegame position
+    let mut weights = rm::Weights::default();
+    let mut seed: u64 = 2;
+    for value in weights.values_mut() {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+        *value += (seed >> 33) as i32 % 21 - 10;
+    }
+    let bases = [
+        "4k3/8/8/8/8/8/8/4K3",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+    ];
+    for piece in ['P', 'N', 'B', 'R', 'Q', 'K'] {
+        let mut checked = 0;
+        for base in bases {
+            for fen in placements(base, piece) {
+                // Positions with the side not to move in check are skipped
+                let (Ok(board), Ok(mirrored)) =
+                    (Board::from_str(&fen), Board::from_str(&mirror(&fen)))
+                else {
+                    continue;
+                };
+                let eval = rm::evaluation(&board, -999_999, 999_999, &weights);
+                let mirrored_eval = rm::evaluation(&mirrored, -999_999, 999_999, &weights);
+                // Both are relative to the side to move, which has the same position
+                assert_eq!(eval, mirrored_eval, "{}", fen);
+                checked += 1;
+            }
+        }
+        assert!(checked > 50, "{}: {} positions", piece, checked);
+    }
+}
@@ -0,0 +1,192 @@
+use chess::{Board, MoveGen};
+use proptest::prelude::*;
+use rusty_mind as rm;
+use std::str::FromStr;
+
+// Positions reached by random games from a few starting positions
+// must survive printing and parsing unchanged
+
+const STARTS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+];
+
+fn random_game(start: usize, choices: &[usize]) -> (Board, Vec<String>) {
+    // Plays the chosen moves, each choice is an index into the legal moves
+    let mut board = Board::from_str(STARTS[start]).unwrap();
+    let mut moves = Vec::new();
+    for choice in choices {
+        let legal: Vec<_> = MoveGen::new_legal(&board).collect();
+        if legal.is_empty() {
+            break;
+        }
+        let mv = legal[choice % legal.len()];
+        moves.push(mv.to_string());
+        board = board.make_move_new(mv);
+    }
+    (board, moves)
+}
+
+fn position() -> impl Strategy<Value = rm::Fen> {
+    (
+        0..STARTS.len(),
+        prop::collection::vec(any::<usize>(), 0..60),
+        0..200u32,
+        1..500u32,
+    )
+        .prop_map(|(start, choices, halfmove, fullmove)| {
+            rm::Fen::new(random_game(start, &choices).0, halfmove, fullmove)
+        })
+}
+
+proptest! {
+    #[test]
+    fn print_parse_print(fen in position()) {
+        let text = fen.to_string();
+        let parsed = rm::Fen::parse(&text).unwrap();
+        prop_assert_eq!(parsed, fen);
+        prop_assert_eq!(parsed.to_string(), text);
+    }
+
+    #[test]
+    fn shredder_round_trip(fen in position()) {
+        let text = fen.to_fen(rm::CastlingNotation::Shredder);
+        let parsed = rm::Fen::parse(&text).unwrap();
+        prop_assert_eq!(parsed, fen);
+        prop_assert_eq!(parsed.to_fen(rm::CastlingNotation::Shredder), text);
+    }
+
+    #[test]
+    fn matches_chess_crate(fen in position()) {
+        // The chess crate must read our FEN as the same position
+        let board = Board::from_str(&fen.to_string()).unwrap();
+        prop_assert_eq!(board, fen.board);
+    }
+
+    #[test]
+    fn game_counters(
+        start in 0..STARTS.len(),
+        choices in prop::collection::vec(any::<usize>(), 0..40),
+    ) {
+        // The FEN of a game continues the counters of its starting position
+        // and sets up the same game again
+        let (board, moves) = random_game(start, &choices);
+        let mut game = rm::Game::with_cache_size(1 << 10);
+        game.set_board(STARTS[start], moves.iter().map(|x| x.as_str()).collect()).unwrap();
+        let text = game.fen();
+        let fen = rm::Fen::parse(&text).unwrap();
+        prop_assert_eq!(fen.board, board);
+        let start = rm::Fen::parse(STARTS[start]).unwrap();
+        let black_to_move = start.board.side_to_move() == chess::Color::Black;
+        let plies = 2 * (start.fullmove - 1) + black_to_move as u32;
+        prop_assert_eq!(fen.fullmove, (plies + moves.len() as u32) / 2 + 1);
+
+        let mut copy = rm::Game::with_cache_size(1 << 10);
+        copy.set_board(&text, Vec::new()).unwrap();
+        prop_assert_eq!(copy.fen(), text);
+    }
+}
+
+#[test]
+fn en_passant_and_counters() {
+    let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+    assert_eq!(rm::Fen::parse(fen).unwrap().to_string(), fen);
+
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_board(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        vec!["e4", "Nf6", "e5", "Ng8", "Nf3", "d5"],
+    )
+    .unwrap();
+    assert_eq!(
+        game.fen(),
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/5N2/PPPP1PPP/RNBQKB1R w KQkq d6 0 4"
+    );
+    game.set_board(&game.fen(), vec!["Nc3", "Nf6"]).unwrap();
+    assert_eq!(
+        game.fen(),
+        "rnbqkb1r/ppp1pppp/5n2/3pP3/8/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 2 5"
+    );
+}
+
+#[test]
+fn castling_notations() {
+    // Standard, Shredder-FEN and X-FEN letters describe the same rights
+    let standard = rm::Fen::parse(STARTS[1]).unwrap();
+    let shredder = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w HAha - 0 1";
+    assert_eq!(rm::Fen::parse(shredder).unwrap(), standard);
+    assert_eq!(standard.to_fen(rm::CastlingNotation::Shredder), shredder);
+    let mixed = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KAhq - 0 1";
+    assert_eq!(rm::Fen::parse(mixed).unwrap(), standard);
+}
+
+#[test]
+fn invalid_fens() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkX - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 x",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 2",
+    ] {
+        assert!(rm::Fen::parse(fen).is_err(), "{}", fen);
+    }
+}
+
+#[test]
+fn fullmove_zero() {
+    // Read as 1, the engine used to write 0 itself
+    let fen = "8/8/8/8/8/8/8/K6k w - - 0 0";
+    assert_eq!(rm::Fen::parse(fen).unwrap().fullmove, 1);
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_board(fen, Vec::new()).unwrap();
+    assert_eq!(game.fen(), "8/8/8/8/8/8/8/K6k w - - 0 1");
+    game.set_depth(2);
+    let mv = game.play();
+    assert!(game.board.legal(mv));
+
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_chess960(true);
+    game.set_board(fen, Vec::new()).unwrap();
+    assert_eq!(game.fen(), "8/8/8/8/8/8/8/K6k w - - 0 1");
+}
+
+#[test]
+fn malformed_fen_keeps_the_game() {
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_board(rm::STARTING_FEN, vec!["e4"]).unwrap();
+    let fen = game.fen();
+    for text in [
+        "8/8/8/8/8/8/8/K6k w - -- 0 1",
+        "not a fen",
+        "8/8/8/8/8/8/8/K6k w - - 0 1 2",
+    ] {
+        assert!(game.set_board(text, Vec::new()).is_err(), "{}", text);
+        assert_eq!(game.fen(), fen);
+    }
+    game.set_chess960(true);
+    assert!(game.set_board("not a fen", Vec::new()).is_err());
+}
+
+#[test]
+fn en_passant_without_capture() {
+    // No black pawn can take on e3, the square is kept anyway
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let parsed = rm::Fen::parse(fen).unwrap();
+    assert_eq!(parsed.to_string(), fen);
+    assert_eq!(parsed.board.en_passant(), None);
+
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_board(fen, Vec::new()).unwrap();
+    assert_eq!(game.fen(), fen);
+    game.set_board(fen, vec!["e6"]).unwrap();
+    assert_eq!(
+        game.fen(),
+        "rnbqkbnr/pppp1ppp/4p3/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+    );
+}
@@ -0,0 +1,83 @@
+use rusty_mind as rm;
+
+fn game() -> rm::Game {
+    // Two moves of the player, each followed by a searched reply
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_depth(1);
+    for text in ["e4", "a3"] {
+        let mv = game.parse_move(text).unwrap();
+        game.make_move(mv);
+        let reply = game.play();
+        game.make_move(reply);
+    }
+    game
+}
+
+fn moves(game: &rm::Game) -> Vec<(chess::ChessMove, Option<i32>, Option<i32>)> {
+    game.history()
+        .iter()
+        .map(|x| (x.chess_move, x.score, x.depth))
+        .collect()
+}
+
+#[test]
+fn search_info_is_kept() {
+    let mut game = game();
+    let played = moves(&game);
+    assert_eq!(played.len(), 4);
+    assert_eq!((played[0].1, played[0].2), (None, None));
+    assert!(played[1].1.is_some());
+    assert_eq!(played[1].2, Some(1));
+    let fen = game.fen();
+
+    for _ in 0..4 {
+        assert!(game.undo().is_some());
+    }
+    assert_eq!(game.undo(), None);
+    assert_eq!(game.fen(), rm::STARTING_FEN);
+    for _ in 0..4 {
+        assert!(game.redo().is_some());
+    }
+    assert_eq!(game.redo(), None);
+    assert_eq!(moves(&game), played);
+    assert_eq!(game.fen(), fen);
+}
+
+#[test]
+fn go_to_bounds() {
+    let mut game = game();
+    let played = moves(&game);
+    let fen = game.fen();
+
+    assert!(game.go_to(0));
+    assert!(game.history().is_empty());
+    assert_eq!(game.fen(), rm::STARTING_FEN);
+    assert_eq!(game.next_redo().unwrap().chess_move, played[0].0);
+
+    assert!(game.go_to(played.len()));
+    assert_eq!(moves(&game), played);
+    assert_eq!(game.fen(), fen);
+    assert!(game.next_redo().is_none());
+
+    // Past the end nothing changes, also when some moves are waiting for redo
+    assert!(!game.go_to(played.len() + 1));
+    assert_eq!(game.fen(), fen);
+    assert!(game.go_to(1));
+    assert!(!game.go_to(played.len() + 1));
+    assert_eq!(moves(&game), played[..1]);
+    assert_eq!(game.next_redo().unwrap().chess_move, played[1].0);
+}
+
+#[test]
+fn new_move_clears_redo() {
+    let mut game = game();
+    game.undo();
+    game.undo();
+    assert!(game.next_redo().is_some());
+    let mv = game.parse_move("c4").unwrap();
+    game.make_move(mv);
+    assert!(game.next_redo().is_none());
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.history().len(), 3);
+    assert!(!game.go_to(4));
+}
@@ -0,0 +1,173 @@
+use chess::Color;
+use rusty_mind as rm;
+use std::str::FromStr;
+
+fn game() -> rm::Game {
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_depth(1);
+    game
+}
+
+fn output(command: impl FnOnce(&mut Vec<u8>)) -> String {
+    let mut console = Vec::new();
+    command(&mut console);
+    String::from_utf8(console).unwrap()
+}
+
+#[test]
+fn board_and_flip() {
+    let game = game();
+    let board = output(|console| rm::board(&game, ".board ascii", false, console));
+    let lines: Vec<&str> = board.lines().collect();
+    assert_eq!(lines[1], "8 | r n b q k b n r |");
+    assert_eq!(lines[8], "1 | R N B Q K B N R |");
+    assert_eq!(lines[10], "   a b c d e f g h");
+    assert_eq!(lines[11], "White to move");
+    // Seen from black's side
+    let flipped = output(|console| rm::board(&game, ".board ascii", true, console));
+    let lines: Vec<&str> = flipped.lines().collect();
+    assert_eq!(lines[1], "1 | R N B K Q B N R |");
+    assert_eq!(lines[10], "   h g f e d c b a");
+    // Unicode figures by default
+    let unicode = output(|console| rm::board(&game, ".board", false, console));
+    assert!(unicode.contains("8 | ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜ |"));
+}
+
+#[test]
+fn move_gets_a_reply() {
+    let mut game = game();
+    let text = output(|console| rm::human_move(&mut game, ".move e4", false, console));
+    assert!(text.contains("Engine plays"), "{}", text);
+    assert!(text.ends_with("White to move\n"), "{}", text);
+    let history = game.history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].san, "e4");
+    assert_eq!(history[0].score, None);
+    assert!(history[1].score.is_some());
+
+    let text = output(|console| rm::human_move(&mut game, ".move Ke3", false, console));
+    assert_eq!(text, "Illegal move: Ke3\n");
+}
+
+#[test]
+fn undo_and_redo_move_pairs() {
+    let mut game = game();
+    let text = output(|console| rm::undo(&mut game, false, console));
+    assert_eq!(text, "No moves to take back\n");
+    output(|console| rm::human_move(&mut game, ".move d4", false, console));
+    let reply = game.history()[1].clone();
+
+    // The engine's reply and the player's move are taken back together
+    let text = output(|console| rm::undo(&mut game, false, console));
+    assert!(text.ends_with("White to move\n"));
+    assert!(game.history().is_empty());
+    let text = output(|console| rm::undo(&mut game, false, console));
+    assert_eq!(text, "No moves to take back\n");
+
+    // And replayed together with the search info of the reply
+    output(|console| rm::redo(&mut game, false, console));
+    assert_eq!(game.history().len(), 2);
+    assert_eq!(game.history()[1].chess_move, reply.chess_move);
+    assert_eq!(game.history()[1].score, reply.score);
+    let text = output(|console| rm::redo(&mut game, false, console));
+    assert_eq!(text, "No moves to replay\n");
+
+    // A move without a reply is taken back alone
+    let mv = game.parse_move("Nf3").unwrap();
+    game.make_move(mv);
+    output(|console| rm::undo(&mut game, false, console));
+    assert_eq!(game.history().len(), 2);
+    assert_eq!(game.board.side_to_move(), Color::White);
+    output(|console| rm::redo(&mut game, false, console));
+    assert_eq!(game.history().len(), 3);
+    assert_eq!(game.board.side_to_move(), Color::Black);
+}
+
+#[test]
+fn go_to_ply() {
+    let mut game = game();
+    output(|console| rm::human_move(&mut game, ".move e4", false, console));
+    let text = output(|console| rm::go_to(&mut game, ".goto 1", false, console));
+    assert!(text.ends_with("Black to move\n"));
+    assert_eq!(game.history().len(), 1);
+    for input in [".goto 3", ".goto", ".goto x"] {
+        let text = output(|console| rm::go_to(&mut game, input, false, console));
+        assert_eq!(text, "Invalid value\n", "{}", input);
+        assert_eq!(game.history().len(), 1);
+    }
+    output(|console| rm::go_to(&mut game, ".goto 2", false, console));
+    assert_eq!(game.history().len(), 2);
+}
+
+#[test]
+fn game_over() {
+    let mut game = game();
+    game.set_board(rm::STARTING_FEN, vec!["f3", "e5", "g4"])
+        .unwrap();
+    let text = output(|console| rm::engine_move(&mut game, false, console));
+    assert!(text.contains("Engine plays Qh4#"), "{}", text);
+    assert!(text.ends_with("Game over: 0-1 (checkmate)\n"), "{}", text);
+    let text = output(|console| rm::human_move(&mut game, ".move a3", false, console));
+    assert_eq!(text, "Game over: 0-1 (checkmate)\n");
+}
+
+#[test]
+fn spsa_options() {
+    // Every line of the SPSA input names an option which setoption accepts within its range
+    let text = output(rm::spsa);
+    assert_eq!(text.lines().count(), rm::TUNABLES.len());
+    let mut game = game();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split(", ").collect();
+        assert_eq!(fields.len(), 7, "{}", line);
+        assert_eq!(fields[1], "int");
+        assert_eq!(fields[6], "0.002");
+        let name = fields[0];
+        let [default, min, max, step] =
+            [2, 3, 4, 5].map(|index| fields[index].parse::<i32>().unwrap());
+        assert!(min <= default && default <= max && step > 0, "{}", line);
+        assert_eq!(game.tunable(name), Some(default), "{}", name);
+
+        for value in [min, max] {
+            let command = format!("setoption name {} value {}", name, value);
+            assert_eq!(
+                output(|console| rm::set_option(&mut game, &command, console)),
+                ""
+            );
+            assert_eq!(game.tunable(name), Some(value), "{}", command);
+        }
+        for value in [min - 1, max + 1] {
+            let command = format!("setoption name {} value {}", name, value);
+            let text = output(|console| rm::set_option(&mut game, &command, console));
+            assert_eq!(text, "Invalid value\n", "{}", command);
+            assert_eq!(game.tunable(name), Some(max), "{}", command);
+        }
+        let command = format!("setoption name {} value {}", name, default);
+        output(|console| rm::set_option(&mut game, &command, console));
+    }
+    assert_eq!(game.tunable("Unknown"), None);
+    let text =
+        output(|console| rm::set_option(&mut game, "setoption name Unknown value 1", console));
+    assert_eq!(text, "Unknown option: Unknown\n");
+}
+
+#[test]
+fn tunables_keep_the_bench() {
+    // Setting every option away and back to its default searches the same nodes as a fresh engine
+    let fens = &rm::BENCH_FENS[5..10];
+    let nodes: Vec<u64> = fens.iter().map(|fen| rm::bench_position(fen, 1)).collect();
+    for (fen, &count) in fens.iter().zip(&nodes) {
+        let board = chess::Board::from_str(fen).unwrap();
+        let mut engine = rm::Engine::with_cache_size(rm::BENCH_CACHE_SIZE);
+        engine.search(&board, 1);
+        for tunable in &rm::TUNABLES {
+            assert!(engine.set_tunable(tunable.name, tunable.max));
+            assert!(engine.set_tunable(tunable.name, tunable.default));
+        }
+        engine.reset_nodes();
+        engine.search(&board, 1);
+        assert_eq!(engine.nodes(), count, "{}", fen);
+    }
+    let again: Vec<u64> = fens.iter().map(|fen| rm::bench_position(fen, 1)).collect();
+    assert_eq!(again, nodes);
+}
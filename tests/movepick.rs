@@ -0,0 +1,78 @@
+use chess::{Board, ChessMove, MoveGen};
+use rusty_mind as rm;
+use std::str::FromStr;
+
+const POSITIONS: [&str; 4] = [
+    rm::STARTING_FEN,
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+];
+
+fn picked(
+    board: &Board,
+    tt_move: Option<ChessMove>,
+    killers: [Option<ChessMove>; 2],
+) -> Vec<ChessMove> {
+    let history = rm::History::new();
+    let mut picker = rm::MovePicker::new(board, tt_move, killers);
+    let mut moves = Vec::new();
+    while let Some(mv) = picker.next(board, &history) {
+        moves.push(mv);
+    }
+    moves
+}
+
+fn mv(text: &str) -> ChessMove {
+    ChessMove::from_str(text).unwrap()
+}
+
+#[test]
+fn every_legal_move_once() {
+    // The hash move and the killers may be illegal, e.g. after a hash collision
+    let guesses = [
+        None,
+        Some(mv("e2e4")),
+        Some(mv("a1b1")),
+        Some(mv("e5d6")),
+        Some(mv("d7c8q")),
+    ];
+    for fen in POSITIONS {
+        let board = Board::from_str(fen).unwrap();
+        let mut legal: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        legal.sort();
+        for tt_move in guesses {
+            for killer in guesses {
+                let mut moves = picked(&board, tt_move, [killer, Some(mv("g1f3"))]);
+                if let Some(first) = tt_move.filter(|x| board.legal(*x)) {
+                    assert_eq!(moves[0], first);
+                }
+                moves.sort();
+                assert_eq!(moves, legal, "{}", fen);
+            }
+        }
+    }
+}
+
+#[test]
+fn captures_before_quiet_moves() {
+    // Winning captures come first, by victim value, and the losing ones last
+    let board = Board::from_str("4k3/8/3p4/4r3/3Q1P2/8/8/4K3 w - - 0 1").unwrap();
+    let moves = picked(&board, None, [None; 2]);
+    assert_eq!(moves[0], mv("f4e5"));
+    assert_eq!(*moves.last().unwrap(), mv("d4e5"));
+}
+
+#[test]
+fn static_exchange() {
+    let board = Board::from_str("4k3/8/3p4/4r3/3Q1P2/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(rm::see(&board, mv("f4e5")), 500);
+    assert_eq!(rm::see(&board, mv("d4e5")), -300);
+    assert_eq!(rm::see(&board, mv("d4d6")), 100);
+    // The rook behind the queen joins the exchange
+    let board = Board::from_str("4k3/3r4/3p4/8/3Q4/3R4/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(rm::see(&board, mv("d4d6")), -300);
+    let board = Board::from_str(POSITIONS[3]).unwrap();
+    // En passant
+    assert_eq!(rm::see(&board, mv("e5d6")), 100);
+}
@@ -0,0 +1,95 @@
+use chess::{Board, ChessMove};
+use rusty_mind as rm;
+use std::str::FromStr;
+
+fn board(fen: &str) -> Board {
+    Board::from_str(fen).unwrap()
+}
+
+fn mv(text: &str) -> ChessMove {
+    ChessMove::from_str(text).unwrap()
+}
+
+fn assert_san(board: &Board, uci: &str, san: &str) {
+    assert_eq!(rm::to_san(board, mv(uci)), san);
+    assert_eq!(rm::parse_san(board, san), Some(mv(uci)), "{}", san);
+}
+
+#[test]
+fn file_and_rank_disambiguation() {
+    let knights = board("4k3/8/8/8/8/8/8/1N1K1N2 w - - 0 1");
+    assert_san(&knights, "b1d2", "Nbd2");
+    assert_san(&knights, "f1d2", "Nfd2");
+    // Only one knight reaches c3
+    assert_san(&knights, "b1c3", "Nc3");
+
+    let rooks = board("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+    assert_san(&rooks, "a1a3", "R1a3");
+    assert_san(&rooks, "a5a3", "R5a3");
+}
+
+#[test]
+fn square_disambiguation() {
+    // The queen on a1 shares its file with a3 and its rank with c1
+    let queens = board("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1");
+    assert_san(&queens, "a1b2", "Qa1b2");
+    // The file of the queen on a3 is shared, its rank is not
+    assert_san(&queens, "a3b2", "Q3b2");
+    assert_san(&queens, "c1b2", "Qcb2");
+}
+
+#[test]
+fn promotions() {
+    let board = board("1r5k/P7/8/8/8/8/8/K7 w - - 0 1");
+    // The rook on b8 blocks the check
+    assert_san(&board, "a7a8q", "a8=Q");
+    assert_san(&board, "a7a8n", "a8=N");
+    assert_san(&board, "a7b8q", "axb8=Q+");
+    // The '=' and the suffix are optional
+    assert_eq!(rm::parse_san(&board, "a8Q"), Some(mv("a7a8q")));
+    assert_eq!(rm::parse_san(&board, "a8=Q"), Some(mv("a7a8q")));
+    assert_eq!(rm::parse_san(&board, "axb8R"), Some(mv("a7b8r")));
+    assert_eq!(rm::parse_san(&board, "a8"), None);
+}
+
+#[test]
+fn check_and_mate_suffixes() {
+    let board = board("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1");
+    assert_san(&board, "b1b8", "Qb8#");
+    assert_san(&board, "b1h1", "Qh1+");
+    assert_san(&board, "b1b3", "Qb3");
+    for san in ["Qb8", "Qb8+", "Qb8#", "Qb8!?"] {
+        assert_eq!(rm::parse_san(&board, san), Some(mv("b1b8")), "{}", san);
+    }
+}
+
+#[test]
+fn castling() {
+    let rights = board("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_san(&rights, "e1g1", "O-O");
+    assert_san(&rights, "e1c1", "O-O-O");
+    assert_eq!(rm::parse_san(&rights, "0-0"), Some(mv("e1g1")));
+    assert_eq!(rm::parse_san(&rights, "0-0-0"), Some(mv("e1c1")));
+    // No castling rights, or pieces in the way
+    let no_rights = board("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1");
+    assert_eq!(rm::parse_san(&no_rights, "O-O"), None);
+    assert_eq!(rm::parse_san(&Board::default(), "O-O-O"), None);
+}
+
+#[test]
+fn rejected_moves() {
+    let knights = board("4k3/8/8/8/8/8/8/1N1K1N2 w - - 0 1");
+    // Ambiguous without the file or the rank
+    assert_eq!(rm::parse_san(&knights, "Nd2"), None);
+    let rooks = board("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(rm::parse_san(&rooks, "Ra3"), None);
+    assert_eq!(rm::parse_san(&rooks, "Raa3"), None);
+    // Illegal or invalid
+    let start = Board::default();
+    assert_eq!(rm::parse_san(&start, "Qh5"), None);
+    assert_eq!(rm::parse_san(&start, "e5"), None);
+    assert_eq!(rm::parse_san(&start, "Xe4"), None);
+    assert_eq!(rm::parse_san(&start, ""), None);
+    assert_eq!(rm::parse_san(&start, "e4"), Some(mv("e2e4")));
+    assert_eq!(rm::parse_san(&start, "Nf3"), Some(mv("g1f3")));
+}
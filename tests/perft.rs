@@ -0,0 +1,178 @@
+use chess::{Board, MoveGen};
+use rusty_mind as rm;
+
+// Standard perft positions from the Chess Programming Wiki
+// https://www.chessprogramming.org/Perft_Results
+
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+fn game(fen: &str, moves: &[&str]) -> rm::Game {
+    // The hash isn't used by perft, a small one keeps the tests light
+    let mut game = rm::Game::with_cache_size(1 << 10);
+    game.set_board(fen, moves.to_vec()).unwrap();
+    game
+}
+
+fn assert_perft(fen: &str, moves: &[&str], expected: &[u64]) {
+    // Both move generators must give the expected counts
+    let game = game(fen, moves);
+    let mut position = rm::Position::from_board(&game.board, 0, 1);
+    for (depth, nodes) in expected.iter().enumerate() {
+        assert_eq!(
+            rm::perft(&game.board, depth as u32 + 1),
+            *nodes,
+            "perft {} of {} moves {:?}",
+            depth + 1,
+            fen,
+            moves
+        );
+        assert_eq!(
+            rm::perft_with(&mut position, depth as u32 + 1),
+            *nodes,
+            "own move generator, perft {} of {} moves {:?}",
+            depth + 1,
+            fen,
+            moves
+        );
+    }
+}
+
+#[test]
+fn startpos() {
+    assert_perft(STARTPOS, &[], &[20, 400, 8_902, 197_281]);
+}
+
+#[test]
+fn kiwipete() {
+    assert_perft(KIWIPETE, &[], &[48, 2_039, 97_862]);
+}
+
+#[test]
+fn position_3() {
+    assert_perft(POSITION_3, &[], &[14, 191, 2_812, 43_238]);
+}
+
+#[test]
+fn position_4() {
+    assert_perft(POSITION_4, &[], &[6, 264, 9_467]);
+}
+
+#[test]
+fn position_5() {
+    assert_perft(POSITION_5, &[], &[44, 1_486, 62_379]);
+}
+
+#[test]
+fn position_6() {
+    assert_perft(POSITION_6, &[], &[46, 2_079, 89_890]);
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let game = game(KIWIPETE, &[]);
+    let counts = rm::divide(&game.board, 3);
+    assert_eq!(counts.len(), 48);
+    assert_eq!(counts.iter().map(|(_, x)| x).sum::<u64>(), 97_862);
+}
+
+#[test]
+fn moves_match_fen() {
+    // Playing moves with set_board must give the same tree as setting up the resulting FEN
+    let cases: [(&str, &[&str], &str); 4] = [
+        // Double pawn pushes
+        (
+            STARTPOS,
+            &["e2e4", "c7c5", "g1f3"],
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+        ),
+        // Castling on both sides
+        (
+            KIWIPETE,
+            &["e1g1", "e8c8"],
+            "2kr3r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R4RK1 w - - 2 2",
+        ),
+        // En passant capture
+        (
+            STARTPOS,
+            &["e2e4", "a7a6", "e4e5", "d7d5", "e5d6"],
+            "rnbqkbnr/1pp1pppp/p2P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3",
+        ),
+        // Promotion with capture and underpromotion
+        (
+            POSITION_5,
+            &["d7c8n", "f2d1"],
+            "rnNq1k1r/pp2bppp/2p5/8/2B5/8/PPP1N1PP/RNBnK2R w KQ - 0 9",
+        ),
+    ];
+    for (fen, moves, expected) in cases {
+        let played = game(fen, moves);
+        let reference = game(expected, &[]);
+        assert_eq!(played.board, reference.board, "{} moves {:?}", fen, moves);
+        assert_eq!(
+            rm::perft(&played.board, 3),
+            rm::perft(&reference.board, 3),
+            "{} moves {:?}",
+            fen,
+            moves
+        );
+    }
+}
+
+fn compare_tree(position: &mut rm::Position, board: &Board, depth: u32) {
+    // Walks the tree with make/unmake and compares every node with the chess crate's board
+    assert_eq!(position.to_board(), *board);
+    assert_eq!(position.hash(), rm::polyglot_key(board), "{}", board);
+    assert_eq!(position.hash(), position.compute_hash(), "{}", board);
+    assert_eq!(position.in_check(), board.checkers().popcnt() > 0);
+    let mut own: Vec<String> = position
+        .legal_moves()
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    let mut reference: Vec<String> = MoveGen::new_legal(board).map(|x| x.to_string()).collect();
+    own.sort();
+    reference.sort();
+    assert_eq!(own, reference, "{}", board);
+    if depth == 0 {
+        return;
+    }
+    for mv in position.legal_moves().iter() {
+        position.make_move(*mv);
+        compare_tree(
+            position,
+            &board.make_move_new(mv.to_chess_move()),
+            depth - 1,
+        );
+        position.unmake_move();
+    }
+}
+
+#[test]
+fn own_move_generator_matches_chess_crate() {
+    for fen in [
+        STARTPOS, KIWIPETE, POSITION_3, POSITION_4, POSITION_5, POSITION_6,
+    ] {
+        let game = game(fen, &[]);
+        let mut position = rm::Position::from_board(&game.board, 0, 1);
+        compare_tree(&mut position, &game.board, 2);
+        // make and unmake restore the position exactly
+        assert_eq!(position.to_board(), game.board);
+    }
+}
+
+#[test]
+fn backends_agree() {
+    let game = game(KIWIPETE, &[]);
+    let mut copy_make = rm::CopyMake::new(game.board);
+    let mut position = rm::Position::from_board(&game.board, 0, 1);
+    assert_eq!(
+        rm::perft_with(&mut copy_make, 3),
+        rm::perft_with(&mut position, 3)
+    );
+    assert_eq!(*copy_make.board(), game.board);
+}
@@ -0,0 +1,91 @@
+use rusty_mind as rm;
+
+const GAME: &str = r#"[Event "Test \"quoted\""]
+[Site "?"]
+[Result "1-0"]
+
+{Start} 1. e4 e5 $1 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 3. Bb5 {The Ruy
+Lopez} 3... a6 $6 4. Ba4 1-0
+
+"#;
+
+#[test]
+fn reader() {
+    let games = rm::read_games(GAME);
+    assert_eq!(games.len(), 1);
+    let game = &games[0];
+    assert_eq!(game.tag("Event"), Some("Test \"quoted\""));
+    assert_eq!(game.result, "1-0");
+    assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4"]);
+    assert_eq!(
+        game.comments,
+        [(0, "Start".to_string()), (5, "The Ruy Lopez".to_string())]
+    );
+    assert_eq!(game.nags, [(2, 1), (6, 6)]);
+    assert!(game.replay().is_some());
+}
+
+#[test]
+fn variations() {
+    let game = &rm::read_games(GAME)[0];
+    // The variation replaces the third move, the nested one replaces its second move
+    assert_eq!(game.variations.len(), 1);
+    let (start, variation) = &game.variations[0];
+    assert_eq!(*start, 3);
+    assert_eq!(variation.moves, ["f4", "exf4", "Nf3"]);
+    assert_eq!(variation.variations.len(), 1);
+    assert_eq!(variation.variations[0].0, 2);
+    assert_eq!(variation.variations[0].1.moves, ["d5"]);
+}
+
+#[test]
+fn suffix_annotations() {
+    let game = &rm::read_games("1. e4! e5? 2. Nf3!! Nc6?? 3. Bb5!? a6?! *")[0];
+    assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+    assert_eq!(game.nags, [(1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6)]);
+    assert_eq!(game.result, "*");
+}
+
+#[test]
+fn writer_round_trip() {
+    let game = &rm::read_games(GAME)[0];
+    let pgn = game.to_pgn();
+    // The movetext is wrapped, the checks ignore the line breaks
+    let text = pgn.split_whitespace().collect::<Vec<_>>().join(" ");
+    assert!(text.contains("2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6"));
+    assert!(text.contains("e5 $1"));
+    assert!(text.contains("3. Bb5 {The Ruy Lopez} 3... a6 $6"));
+    assert!(pgn.contains(r#"[Event "Test \"quoted\""]"#));
+    assert!(pgn.lines().all(|line| line.len() <= 80));
+    let again = &rm::read_games(&pgn)[0];
+    assert_eq!(again.to_pgn(), pgn);
+    assert_eq!(again.moves, game.moves);
+    assert_eq!(again.tags, game.tags);
+    assert_eq!(again.comments, game.comments);
+    assert_eq!(again.nags, game.nags);
+}
+
+#[test]
+fn comments_between_games() {
+    let text = format!(
+        "{}{{Between the games}}\n\n{}; trailing comment\n",
+        GAME, GAME
+    );
+    let games = rm::read_games(&text);
+    assert_eq!(games.len(), 2);
+    assert!(games.iter().all(|game| game.moves.len() == 7));
+    assert!(rm::read_games("{Only a comment}").is_empty());
+}
+
+#[test]
+fn games_without_tags() {
+    let games = rm::read_games("1. d4 d5 1/2-1/2\n1. c4 0-1\n");
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].result, "1/2-1/2");
+    assert_eq!(games[1].moves, ["c4"]);
+    // The FEN tag sets the first move number
+    let game =
+        &rm::read_games("[FEN \"4k3/8/8/8/8/8/8/4K2R b K - 0 12\"]\n\n12... Kd7 13. O-O *")[0];
+    assert!(game.replay().is_some());
+    assert!(game.to_pgn().contains("12... Kd7 13. O-O *"));
+}
@@ -0,0 +1,121 @@
+use chess::{Board, ChessMove};
+use rusty_mind as rm;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+#[test]
+fn node_limit_stops_inside_the_tree() {
+    let mut engine = rm::Engine::with_cache_size(1 << 16);
+    let board = Board::default();
+    engine.set_node_limit(Some(1_000));
+    assert_eq!(engine.search(&board, 6), None);
+    // Every node returns as soon as the limit is reached
+    assert_eq!(engine.nodes(), 1_000);
+    // Without a limit the search finishes again
+    engine.set_node_limit(None);
+    engine.reset_nodes();
+    assert!(engine.search(&board, 2).is_some());
+}
+
+#[test]
+fn seed_makes_the_root_cushion_reproducible() {
+    let play = |seed: u64| -> Vec<ChessMove> {
+        let mut engine = rm::Engine::with_cache_size(1 << 12);
+        engine.set_seed(seed);
+        assert!(engine.set_tunable("RandomMargin", 200));
+        let mut board = Board::default();
+        let mut moves = Vec::new();
+        for _ in 0..6 {
+            let mv = engine.play(&board, 2).unwrap();
+            board = board.make_move_new(mv);
+            moves.push(mv);
+        }
+        moves
+    };
+    assert_eq!(play(7), play(7));
+    assert_ne!(play(7), play(8));
+}
+
+#[test]
+fn new_weights_clear_the_hash() {
+    let mut engine = rm::Engine::with_cache_size(1 << 12);
+    engine.search(&Board::default(), 2);
+    let board = Board::default().make_move_new(ChessMove::from_str("e2e4").unwrap());
+    assert!(engine.probe_hash(&board).is_some());
+    let mut weights = rm::Weights::default();
+    weights.knight_value += 10;
+    engine.set_weights(weights);
+    assert_eq!(engine.probe_hash(&board), None);
+}
+
+#[test]
+fn tunables_clear_the_hash() {
+    let mut engine = rm::Engine::with_cache_size(1 << 12);
+    let board = Board::default().make_move_new(ChessMove::from_str("e2e4").unwrap());
+    for (name, value) in [("QsearchCheckDepth", 0), ("KnightValue", 330)] {
+        engine.search(&Board::default(), 2);
+        assert!(engine.probe_hash(&board).is_some());
+        assert!(engine.set_tunable(name, value));
+        assert_eq!(engine.probe_hash(&board), None, "{}", name);
+    }
+}
+
+#[test]
+fn deadline_stops_inside_the_tree() {
+    let mut engine = rm::Engine::with_cache_size(1 << 16);
+    engine.set_deadline(Some(Instant::now()));
+    let start = Instant::now();
+    assert_eq!(engine.search(&Board::default(), 8), None);
+    assert!(engine.stopped());
+    assert!(start.elapsed() < Duration::from_secs(1));
+    engine.set_deadline(None);
+    assert!(engine.search(&Board::default(), 1).is_some());
+    assert!(!engine.stopped());
+}
+
+#[test]
+fn analyse_keeps_to_the_time() {
+    let mut game = rm::Game::with_cache_size(1 << 16);
+    let start = Instant::now();
+    let analysis = game.analyse(Duration::from_millis(200), 64).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(analysis.depth >= 1);
+    assert_eq!(analysis.line[0], analysis.best_move);
+}
+
+#[test]
+fn analyse_without_time_limit() {
+    // The annotate binary passes Duration::MAX when only a depth is given
+    let mut game = rm::Game::with_cache_size(1 << 16);
+    let analysis = game.analyse(Duration::MAX, 2).unwrap();
+    assert_eq!(analysis.depth, 2);
+    assert_eq!(analysis.line[0], analysis.best_move);
+}
+
+#[test]
+fn backends_search_the_same_tree() {
+    // The search plays its moves on either backend, they visit the same nodes and agree on the result
+    // Positions with castling rights on both sides, an endgame and a middlegame with captures
+    for index in [0, 2, 7, 10] {
+        let fen = rm::BENCH_FENS[index];
+        let board = Board::from_str(fen).unwrap();
+        let mut results = Vec::new();
+        for backend in 0..2 {
+            let mut engine = rm::Engine::with_cache_size(rm::BENCH_CACHE_SIZE);
+            engine.set_seed(1);
+            let result = if backend == 0 {
+                engine.search_with(&mut rm::CopyMake::new(board), 2)
+            } else {
+                let mut position = rm::Position::from_board(&board, 0, 1);
+                let result = engine.search_with(&mut position, 2);
+                // make and unmake leave the position as it was
+                assert_eq!(position.to_board(), board);
+                result
+            };
+            results.push((result, engine.nodes()));
+        }
+        assert!(results[0].0.is_some());
+        assert_eq!(results[0], results[1], "{}", fen);
+        assert_eq!(results[0].1, rm::bench_position(fen, 2), "{}", fen);
+    }
+}
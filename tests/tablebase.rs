@@ -0,0 +1,82 @@
+use chess::{Board, BoardStatus};
+use rusty_mind as rm;
+use std::env;
+use std::str::FromStr;
+
+// The probing tests read the tables listed in tests/syzygy/SOURCE.txt, download them with
+// (cd tests/syzygy && wget -nc -i SOURCE.txt) and run cargo test -- --ignored
+// SYZYGY_PATH can point to another directory with these tables
+fn syzygy_path() -> String {
+    env::var("SYZYGY_PATH")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy").to_string())
+}
+
+fn tables() -> rm::Tablebase {
+    let path = syzygy_path();
+    let mut tablebase = rm::Tablebase::new();
+    let count = tablebase.set_path(&path).unwrap();
+    assert!(count > 0, "no tables found in {}", path);
+    tablebase
+}
+
+fn board(fen: &str) -> Board {
+    Board::from_str(fen).unwrap()
+}
+
+#[test]
+fn no_tables() {
+    let tablebase = rm::Tablebase::new();
+    let kqk = board("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+    assert!(!tablebase.covers(&kqk));
+    assert_eq!(tablebase.probe_wdl(&kqk), None);
+    assert_eq!(tablebase.best_move(&kqk), None);
+}
+
+#[test]
+#[ignore = "needs the tables in tests/syzygy"]
+fn probe_wdl() {
+    let tablebase = tables();
+    // Scores are relative to the side to move
+    assert_eq!(
+        tablebase.probe_wdl(&board("4k3/8/8/8/8/8/8/3QK3 w - - 0 1")),
+        Some(rm::TB_WIN)
+    );
+    assert_eq!(
+        tablebase.probe_wdl(&board("4k3/8/8/8/8/8/8/3QK3 b - - 0 1")),
+        Some(-rm::TB_WIN)
+    );
+    assert_eq!(
+        tablebase.probe_wdl(&board("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1")),
+        Some(0)
+    );
+    // Too many pieces for the tables
+    assert!(!tablebase.covers(&Board::default()));
+    assert_eq!(tablebase.probe_wdl(&Board::default()), None);
+}
+
+#[test]
+#[ignore = "needs the tables in tests/syzygy"]
+fn best_move() {
+    let tablebase = tables();
+    // Qa8 is the only mate in one
+    let kqk = board("7k/8/6K1/8/8/8/8/Q7 w - - 0 1");
+    let mv = tablebase.best_move(&kqk).unwrap();
+    assert_eq!(kqk.make_move_new(mv).status(), BoardStatus::Checkmate);
+    // A lost position still has a best move
+    let lost = board("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+    let mv = tablebase.best_move(&lost).unwrap();
+    assert!(lost.legal(mv));
+}
+
+#[test]
+#[ignore = "needs the tables in tests/syzygy"]
+fn search_uses_the_tables() {
+    let mut engine = rm::Engine::with_cache_size(1 << 10);
+    let count = engine.set_syzygy_path(&syzygy_path()).unwrap();
+    assert!(count > 0);
+    let (mv, score) = engine
+        .search(&board("7k/8/6K1/8/8/8/8/Q7 w - - 0 1"), 3)
+        .unwrap();
+    assert_eq!(mv.to_string(), "a1a8");
+    assert_eq!(score, rm::TB_WIN);
+}
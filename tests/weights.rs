@@ -0,0 +1,20 @@
+use rusty_mind as rm;
+
+#[test]
+fn toml_round_trip() {
+    let mut weights = rm::Weights::default();
+    for (index, value) in weights.values_mut().into_iter().enumerate() {
+        *value = index as i32;
+    }
+    assert_eq!(rm::Weights::from_toml(&weights.to_toml()), Ok(weights));
+}
+
+#[test]
+fn accessors_cover_every_weight() {
+    let mut weights = rm::Weights::default();
+    assert_eq!(weights.tables().len(), 7);
+    assert_eq!(weights.scalars().len(), 20);
+    assert_eq!(weights.values_mut().len(), 7 * 64 + 20);
+    assert_eq!(weights.scalars()[0], ("PAWN_VALUE", weights.pawn_value));
+    assert!(rm::Weights::from_toml("unknown = 1").is_err());
+}